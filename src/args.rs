@@ -1,20 +1,95 @@
-use clap::Parser;
+use std::path::PathBuf;
+
+use clap::parser::ValueSource;
+use clap::{CommandFactory, FromArgMatches, Parser, Subcommand};
+
+use crate::columns::{ColumnSpec, SortKey};
+use crate::filter::Filter;
+use crate::rules::{MatchField, MatchMode};
 
 #[derive(Parser)]
 #[command(name = "rprocmon")]
 #[command(about = "A Rust-based process monitor for security analysis")]
 pub struct Args {
-    /// Monitor interval in seconds
-    #[arg(short, long, default_value = "2")]
-    pub interval: u64,
+    #[command(subcommand)]
+    pub command: Option<Command>,
+
+    /// Configuration file to use instead of ~/.config/rprocmon/config.toml (or the
+    /// config.yaml, config.yml or config.json found there)
+    #[arg(long, global = true, value_name = "FILE")]
+    pub config: Option<PathBuf>,
+
+    /// Options of the live monitor, which runs when no subcommand is given
+    #[command(flatten)]
+    pub monitor: MonitorArgs,
+}
 
-    /// Save output to JSON file
+impl Args {
+    /// Parse the command line, rejecting monitor options given before another subcommand
+    ///
+    /// clap's `args_conflicts_with_subcommands` would also reject `--config`, which
+    /// applies to every subcommand, so the check is done here.
+    pub fn parse_checked() -> Self {
+        let mut command = Args::command();
+        let matches = command.get_matches_mut();
+        if let Some((name, _)) = matches.subcommand()
+            && let Some(id) = matches.ids().find(|id| {
+                id.as_str() != "config" && matches.value_source(id.as_str()) == Some(ValueSource::CommandLine)
+            }) {
+            command.error(clap::error::ErrorKind::ArgumentConflict,
+                          format!("the subcommand '{}' cannot be used with '--{}'", name, id.as_str().replace('_', "-")))
+                .exit();
+        }
+        Args::from_arg_matches(&matches).unwrap_or_else(|e| e.exit())
+    }
+}
+
+/// Options left out fall back to the configuration file
+#[derive(clap::Args)]
+pub struct MonitorArgs {
+    /// Monitor interval in seconds [default: 2]
+    #[arg(short, long)]
+    pub interval: Option<u64>,
+
+    /// Stream snapshots to an NDJSON file as they are collected
     #[arg(short, long)]
     pub output: Option<String>,
 
-    /// Filter by process name
+    /// Start a new output file once the current one reaches this many megabytes
+    #[arg(long, value_name = "MB")]
+    pub rotate_size: Option<u64>,
+
+    /// Start a new output file after this many seconds
+    #[arg(long, value_name = "SECONDS")]
+    pub rotate_interval: Option<u64>,
+
+    /// Gzip-compress output files (implied by a `.gz` output path)
+    #[arg(long)]
+    pub gzip: bool,
+
+    /// Write delta records (changes only) between periodic full keyframes
+    #[arg(long)]
+    pub delta: bool,
+
+    /// Snapshots between full keyframes in delta mode [default: 60]
+    #[arg(long, value_name = "N")]
+    pub keyframe_interval: Option<u32>,
+
+    /// Redact saved and streamed snapshots with this profile ("default" or one from the configuration)
+    #[arg(long, value_name = "PROFILE")]
+    pub redact: Option<String>,
+
+    /// Number of recent snapshots kept in memory [default: 300]
+    #[arg(long, value_name = "N")]
+    pub history: Option<usize>,
+
+    /// Filter expression, e.g. 'user=www-data and cpu>20 and exe~"/tmp/"'; a bare word matches process names
+    #[arg(short, long, value_name = "EXPR")]
+    pub filter: Option<Filter>,
+
+    /// Only show processes running as this user (name or UID, real or effective)
     #[arg(short, long)]
-    pub filter: Option<String>,
+    pub user: Option<String>,
 
     /// Show network connections
     #[arg(short, long)]
@@ -27,4 +102,227 @@ pub struct Args {
     /// Verbose output
     #[arg(short, long)]
     pub verbose: bool,
-}
\ No newline at end of file
+
+    /// Start in process tree view
+    #[arg(long)]
+    pub tree: bool,
+
+    /// Keep processes excluded by stealth rules, --filter or --user, shown greyed out
+    #[arg(long)]
+    pub show_filtered: bool,
+
+    /// Look for processes hidden from /proc by comparing process listings, /proc lookups
+    /// and kill(pid, 0) for every PID, every SECONDS (default 60)
+    #[arg(long, value_name = "SECONDS", num_args = 0..=1, require_equals = true, default_missing_value = "60")]
+    pub hidden_scan: Option<u64>,
+
+    /// Process list columns in order, each optionally with a width, e.g. "pid,name:30,cpu,mem,exe"
+    /// (pid, name, cpu, mem, ppid, user, group, status, uptime, threads, io, read, write, exe, cmd,
+    /// cpu-history, mem-history)
+    #[arg(long, value_name = "LIST", value_delimiter = ',')]
+    pub columns: Vec<ColumnSpec>,
+
+    /// Sort the process list by this key
+    #[arg(long, value_enum)]
+    pub sort: Option<SortKey>,
+
+    /// Reverse the sort order (e.g. lowest CPU first)
+    #[arg(long)]
+    pub reverse: bool,
+}
+
+#[derive(Subcommand)]
+pub enum Command {
+    /// Monitor processes live (the default when no subcommand is given)
+    Monitor(MonitorArgs),
+
+    /// Manage hidden and renamed processes and the display profile (interactive menu without a subcommand)
+    Config {
+        #[command(subcommand)]
+        action: Option<ConfigCommand>,
+    },
+
+    /// Reconstruct one full snapshot from a capture file
+    Snapshot {
+        /// Capture file written with --output (plain, delta or gzip)
+        file: PathBuf,
+
+        /// Time to reconstruct, e.g. "2026-10-16 14:05:00"; defaults to the last snapshot
+        #[arg(long)]
+        at: Option<String>,
+
+        /// Write the snapshot to this file instead of stdout
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+
+        /// Redact the snapshot with this profile ("default" or one from the configuration)
+        #[arg(long, value_name = "PROFILE")]
+        redact: Option<String>,
+    },
+
+    /// Replay a recorded capture with the live display
+    Replay {
+        /// Capture file (NDJSON, delta, gzip, JSON array or a single saved snapshot)
+        file: PathBuf,
+
+        /// Initial playback speed multiplier
+        #[arg(long, default_value = "1.0")]
+        speed: f64,
+
+        /// Show command lines and executable paths
+        #[arg(short, long)]
+        verbose: bool,
+
+        /// Only show processes matching this filter expression
+        #[arg(short, long, value_name = "EXPR")]
+        filter: Option<Filter>,
+    },
+
+    /// Compare two saved snapshots
+    Diff {
+        /// Earlier snapshot (the last one is used if the file holds several)
+        from: PathBuf,
+
+        /// Later snapshot
+        to: PathBuf,
+
+        /// Print the differences as JSON
+        #[arg(long)]
+        json: bool,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum ConfigCommand {
+    /// Hide processes whose name contains any of these (ignoring case); see `rule add` for other match modes
+    HideName {
+        #[arg(required = true)]
+        names: Vec<String>,
+    },
+
+    /// Stop hiding these process names
+    UnhideName {
+        #[arg(required = true)]
+        names: Vec<String>,
+    },
+
+    /// Hide these PIDs
+    HidePid {
+        #[arg(required = true)]
+        pids: Vec<u32>,
+    },
+
+    /// Stop hiding these PIDs
+    UnhidePid {
+        #[arg(required = true)]
+        pids: Vec<u32>,
+    },
+
+    /// Show processes whose name contains ORIGINAL as DISPLAY
+    Rename {
+        original: String,
+        display: String,
+    },
+
+    /// Remove the rename of ORIGINAL
+    Unrename {
+        original: String,
+    },
+
+    /// Add, list or remove hide and rename rules with explicit match modes
+    Rule {
+        #[command(subcommand)]
+        action: RuleCommand,
+    },
+
+    /// Set the default process list columns, e.g. "pid,name:30,cpu,mem,exe"
+    Columns {
+        #[arg(value_name = "LIST", value_delimiter = ',', required = true)]
+        columns: Vec<ColumnSpec>,
+    },
+
+    /// Set the default sort order of the process list
+    Sort {
+        #[arg(value_enum)]
+        key: SortKey,
+
+        /// Reverse the key's natural direction
+        #[arg(long)]
+        reverse: bool,
+    },
+
+    /// Print the current configuration
+    List {
+        /// Print it as JSON
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Write the configuration to a file (TOML, YAML or JSON by extension), or as JSON to stdout
+    Export {
+        file: Option<PathBuf>,
+    },
+
+    /// Replace the configuration with one exported earlier ("-" reads JSON from stdin)
+    Import {
+        file: PathBuf,
+
+        /// Add the imported hidden processes, PIDs and renames to the current ones instead
+        #[arg(long)]
+        merge: bool,
+    },
+
+    /// Remove all hidden processes, PIDs and renames
+    Clear,
+
+    /// Convert a JSON configuration such as an old stealth_config.json to TOML or YAML
+    Migrate {
+        /// File to convert
+        #[arg(default_value = "stealth_config.json")]
+        from: PathBuf,
+
+        /// Where to write it, by extension [default: ~/.config/rprocmon/config.toml]
+        #[arg(long)]
+        to: Option<PathBuf>,
+
+        /// Overwrite the target if it exists
+        #[arg(long)]
+        force: bool,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum RuleCommand {
+    /// Hide processes matching PATTERN, or show them under another name with --rename
+    Add {
+        pattern: String,
+
+        /// What the pattern is matched against
+        #[arg(long, value_enum, default_value_t)]
+        field: MatchField,
+
+        /// How the pattern is matched
+        #[arg(long = "match", value_name = "MODE", value_enum, default_value_t)]
+        mode: MatchMode,
+
+        /// Match regardless of case
+        #[arg(short = 'i', long)]
+        ignore_case: bool,
+
+        /// Rules with a higher priority are tried first
+        #[arg(long, default_value = "0", allow_negative_numbers = true)]
+        priority: i32,
+
+        /// Show matching processes under this name instead of hiding them
+        #[arg(long, value_name = "NAME")]
+        rename: Option<String>,
+    },
+
+    /// List the rules in the order they are tried
+    List,
+
+    /// Remove a rule by its number in `rule list`
+    Remove {
+        index: usize,
+    },
+}
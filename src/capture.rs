@@ -0,0 +1,532 @@
+//! Streaming capture files: one `MonitorSnapshot` per line (NDJSON).
+//!
+//! Every snapshot is written and flushed as soon as it is collected, so an
+//! interrupted run keeps everything recorded up to that point. In delta mode
+//! each line is a [`CaptureRecord`] instead; [`CaptureReader`] reads both, and
+//! [`CaptureIndex`] finds any snapshot of a long capture without loading it all.
+
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, BufWriter, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use chrono::{DateTime, Local};
+use flate2::read::MultiGzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use serde::Deserialize;
+use serde_json::Value;
+
+use crate::delta::{CaptureRecord, DeltaDecoder, DeltaEncoder};
+use crate::types::MonitorSnapshot;
+
+#[derive(Debug, Clone)]
+pub struct CaptureOptions {
+    pub path: PathBuf,
+    // Start a new segment once the current one reaches this many bytes (uncompressed)
+    pub rotate_size: Option<u64>,
+    // Start a new segment once the current one has been open this long
+    pub rotate_interval: Option<Duration>,
+    pub gzip: bool,
+    // Write delta records with a keyframe every this many snapshots
+    pub keyframe_interval: Option<u32>,
+}
+
+pub struct CaptureWriter {
+    options: CaptureOptions,
+    writer: Box<dyn Write>,
+    encoder: Option<DeltaEncoder>,
+    current_path: PathBuf,
+    bytes_written: u64,
+    opened_at: Instant,
+    segment: u32,
+}
+
+impl CaptureWriter {
+    pub fn create(options: CaptureOptions) -> io::Result<Self> {
+        let current_path = segment_path(&options.path, 0, options.gzip);
+        let writer = open_segment(&current_path, options.gzip)?;
+        // Rotated segments left by an earlier capture to the same path would be
+        // read as the continuation of this one
+        for stale in segments(&current_path).into_iter().skip(1) {
+            std::fs::remove_file(stale)?;
+        }
+
+        Ok(Self {
+            encoder: options.keyframe_interval.map(DeltaEncoder::new),
+            options,
+            writer,
+            current_path,
+            bytes_written: 0,
+            opened_at: Instant::now(),
+            segment: 0,
+        })
+    }
+
+    /// Path of the segment currently being written
+    pub fn current_path(&self) -> &Path {
+        &self.current_path
+    }
+
+    /// Append one snapshot as a single JSON line, rotating first if a limit was reached
+    pub fn write_snapshot(&mut self, snapshot: &MonitorSnapshot) -> io::Result<()> {
+        if self.should_rotate() {
+            self.rotate()?;
+        }
+
+        let mut line = match &mut self.encoder {
+            Some(encoder) => serde_json::to_vec(&encoder.encode(snapshot)?)?,
+            None => serde_json::to_vec(snapshot)?,
+        };
+        line.push(b'\n');
+        self.writer.write_all(&line)?;
+        self.writer.flush()?;
+        self.bytes_written += line.len() as u64;
+        Ok(())
+    }
+
+    /// Flush and close the current segment
+    pub fn finish(mut self) -> io::Result<()> {
+        self.writer.flush()
+    }
+
+    fn should_rotate(&self) -> bool {
+        // Never rotate an empty segment, whatever the limits say
+        if self.bytes_written == 0 {
+            return false;
+        }
+
+        let size_reached = self.options.rotate_size.is_some_and(|limit| self.bytes_written >= limit);
+        let interval_reached = self.options.rotate_interval.is_some_and(|limit| self.opened_at.elapsed() >= limit);
+        size_reached || interval_reached
+    }
+
+    fn rotate(&mut self) -> io::Result<()> {
+        self.writer.flush()?;
+
+        self.segment += 1;
+        self.current_path = segment_path(&self.options.path, self.segment, self.options.gzip);
+        // Dropping the old writer finalizes its gzip trailer
+        self.writer = open_segment(&self.current_path, self.options.gzip)?;
+        self.bytes_written = 0;
+        self.opened_at = Instant::now();
+
+        // Every segment starts with a keyframe so it can be read on its own
+        if let Some(encoder) = &mut self.encoder {
+            encoder.force_keyframe();
+        }
+        Ok(())
+    }
+}
+
+/// `capture.ndjson` for the first segment, then `capture.1.ndjson`, `capture.2.ndjson`, ...
+/// with `.gz` appended when compressing
+fn segment_path(base: &Path, segment: u32, gzip: bool) -> PathBuf {
+    let mut path = if segment == 0 {
+        base.to_path_buf()
+    } else {
+        let stem = base.file_stem().unwrap_or_default().to_string_lossy();
+        let name = match base.extension() {
+            Some(ext) => format!("{}.{}.{}", stem, segment, ext.to_string_lossy()),
+            None => format!("{}.{}", stem, segment),
+        };
+        base.with_file_name(name)
+    };
+
+    if gzip && path.extension().is_none_or(|ext| ext != "gz") {
+        let mut name = path.file_name().unwrap_or_default().to_os_string();
+        name.push(".gz");
+        path.set_file_name(name);
+    }
+
+    path
+}
+
+/// Every segment of a capture, given the path of its first segment: `capture.ndjson`
+/// or, for compressed captures, `capture.ndjson.gz`
+pub fn segments(first: &Path) -> Vec<PathBuf> {
+    // `-o capture.ndjson --gzip` writes capture.ndjson.gz, capture.1.ndjson.gz, ...;
+    // `-o capture.ndjson.gz` writes capture.ndjson.gz, capture.ndjson.1.gz, ...
+    let uncompressed = first.to_str()
+        .and_then(|path| path.strip_suffix(".gz"))
+        .map(PathBuf::from);
+
+    let mut paths = vec![first.to_path_buf()];
+    for segment in 1.. {
+        let next = std::iter::once(segment_path(first, segment, false))
+            .chain(uncompressed.as_deref().map(|base| segment_path(base, segment, true)))
+            .find(|path| path.exists());
+        match next {
+            Some(path) => paths.push(path),
+            None => break,
+        }
+    }
+    paths
+}
+
+fn open_segment(path: &Path, gzip: bool) -> io::Result<Box<dyn Write>> {
+    let file = BufWriter::new(File::create(path)?);
+    if gzip {
+        Ok(Box::new(GzEncoder::new(file, Compression::default())))
+    } else {
+        Ok(Box::new(file))
+    }
+}
+
+/// Reads a capture file line by line, yielding full snapshots
+///
+/// Accepts plain NDJSON snapshots and delta records, gzip-compressed or not.
+pub struct CaptureReader {
+    lines: io::Lines<BufReader<Box<dyn Read>>>,
+    decoder: DeltaDecoder,
+    line_number: usize,
+}
+
+impl CaptureReader {
+    pub fn open(path: &Path) -> io::Result<Self> {
+        Ok(Self::from_reader(open_decompressed(path)?))
+    }
+
+    pub fn from_reader(source: Box<dyn Read>) -> Self {
+        Self::starting_at_line(source, 0)
+    }
+
+    /// Read from the middle of a file, numbering lines for errors as in the whole file
+    fn starting_at_line(source: Box<dyn Read>, line_number: usize) -> Self {
+        Self {
+            lines: BufReader::new(source).lines(),
+            decoder: DeltaDecoder::new(),
+            line_number,
+        }
+    }
+
+    fn parse_line(&mut self, line: &str) -> io::Result<MonitorSnapshot> {
+        self.parse_value(serde_json::from_str(line)?)
+    }
+
+    fn parse_value(&mut self, value: Value) -> io::Result<MonitorSnapshot> {
+        let mut snapshot = if value.get("record").is_some() {
+            let record: CaptureRecord = serde_json::from_value(value)?;
+            self.decoder.apply(record)?
+        } else {
+            serde_json::from_value(value)?
+        };
+        snapshot.resolve_finding_processes();
+        Ok(snapshot)
+    }
+}
+
+impl Iterator for CaptureReader {
+    type Item = io::Result<MonitorSnapshot>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let line = match self.lines.next()? {
+                Ok(line) => line,
+                // A gzip segment whose writer was killed has no trailer; its records end there
+                Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => return None,
+                Err(e) => return Some(Err(e)),
+            };
+            self.line_number += 1;
+
+            if line.trim().is_empty() {
+                continue;
+            }
+
+            let line_number = self.line_number;
+            return Some(self.parse_line(&line).map_err(|e| {
+                io::Error::new(io::ErrorKind::InvalidData, format!("line {}: {}", line_number, e))
+            }));
+        }
+    }
+}
+
+/// Open a file for reading, transparently decompressing gzip
+fn open_decompressed(path: &Path) -> io::Result<Box<dyn Read>> {
+    open_at(path, 0)
+}
+
+/// Open a file `offset` bytes into its decompressed content
+///
+/// Plain files seek there; gzip has no random access, so it is decompressed up to it.
+fn open_at(path: &Path, offset: u64) -> io::Result<Box<dyn Read>> {
+    let mut file = BufReader::new(File::open(path)?);
+    // Detect gzip by its magic bytes rather than trusting the extension
+    let is_gzip = file.fill_buf()?.starts_with(&[0x1f, 0x8b]);
+    if is_gzip {
+        let mut decoder = MultiGzDecoder::new(file);
+        io::copy(&mut (&mut decoder).take(offset), &mut io::sink())?;
+        Ok(Box::new(decoder))
+    } else {
+        file.seek(SeekFrom::Start(offset))?;
+        Ok(Box::new(file))
+    }
+}
+
+/// Where one snapshot of a capture is stored
+struct IndexEntry {
+    segment: usize,
+    // Position of the record in the decompressed segment
+    offset: u64,
+    line: usize,
+    timestamp: DateTime<Local>,
+    // Entry decoding starts from: this one for full snapshots, the last keyframe for deltas
+    keyframe: usize,
+}
+
+/// The fields of a capture line needed to index it
+#[derive(Deserialize)]
+struct RecordHeader {
+    #[serde(default)]
+    record: Option<String>,
+    timestamp: DateTime<Local>,
+}
+
+/// A reader positioned in one segment, and the index of the next snapshot it yields
+struct Cursor {
+    segment: usize,
+    next: usize,
+    reader: CaptureReader,
+}
+
+/// Random access to the snapshots of a capture, rotated segments included
+///
+/// Opening a capture reads it once to note where each snapshot starts; only
+/// that position and timestamp are kept, so captures of any length can be
+/// replayed. A snapshot is decoded when asked for, from the keyframe before it,
+/// and reading forward one snapshot at a time continues where the last read ended.
+pub struct CaptureIndex {
+    segments: Vec<PathBuf>,
+    entries: Vec<IndexEntry>,
+    // Files holding one JSON document (a saved snapshot or an array) are small and kept parsed
+    documents: Vec<MonitorSnapshot>,
+    cursor: Option<Cursor>,
+    // The snapshot read last, which replay asks for again as the previous frame
+    last: Option<(usize, MonitorSnapshot)>,
+}
+
+impl CaptureIndex {
+    /// Index a file in any format rPocMon writes: an NDJSON capture (plain or delta,
+    /// gzip-compressed or not, followed by its rotated segments), a pretty-printed
+    /// snapshot saved from the monitor, or a JSON array of snapshots
+    pub fn open(path: &Path) -> io::Result<Self> {
+        let mut index = Self {
+            segments: segments(path),
+            entries: Vec::new(),
+            documents: Vec::new(),
+            cursor: None,
+            last: None,
+        };
+
+        for segment in 0..index.segments.len() {
+            if !index.index_segment(segment)? {
+                index.documents = load_document(path)?;
+                break;
+            }
+        }
+        Ok(index)
+    }
+
+    /// Note every record of a segment; false when the file is not NDJSON
+    fn index_segment(&mut self, segment: usize) -> io::Result<bool> {
+        let path = &self.segments[segment];
+        let mut source = BufReader::new(open_decompressed(path)?);
+        let mut line = String::new();
+        let (mut offset, mut line_number) = (0, 0);
+        let mut keyframe = None;
+
+        loop {
+            line.clear();
+            let read = match source.read_line(&mut line) {
+                Ok(read) => read,
+                // Missing gzip trailer, as CaptureReader reads it
+                Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => 0,
+                Err(e) => return Err(e),
+            };
+            if read == 0 {
+                return Ok(true);
+            }
+            line_number += 1;
+            let start = offset;
+            offset += read as u64;
+            if line.trim().is_empty() {
+                continue;
+            }
+
+            let header: RecordHeader = match serde_json::from_str(&line) {
+                Ok(header) => header,
+                // A document spread over several lines, e.g. a pretty-printed snapshot
+                Err(_) if segment == 0 && self.entries.is_empty() => return Ok(false),
+                Err(e) => return Err(io::Error::new(io::ErrorKind::InvalidData,
+                                                    format!("{}: line {}: {}", path.display(), line_number, e))),
+            };
+            let is_delta = header.record.as_deref() == Some("delta");
+            if !is_delta {
+                keyframe = Some(self.entries.len());
+            }
+            let keyframe = keyframe.ok_or_else(|| {
+                io::Error::new(io::ErrorKind::InvalidData,
+                               format!("{}: line {}: delta record before the first keyframe", path.display(), line_number))
+            })?;
+
+            self.entries.push(IndexEntry {
+                segment,
+                offset: start,
+                line: line_number - 1,
+                timestamp: header.timestamp,
+                keyframe,
+            });
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        if self.documents.is_empty() { self.entries.len() } else { self.documents.len() }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn timestamp(&self, index: usize) -> DateTime<Local> {
+        match self.documents.get(index) {
+            Some(snapshot) => snapshot.timestamp,
+            None => self.entries[index].timestamp,
+        }
+    }
+
+    /// Decode the snapshot at `index`
+    pub fn get(&mut self, index: usize) -> io::Result<MonitorSnapshot> {
+        if let Some(snapshot) = self.documents.get(index) {
+            return Ok(snapshot.clone());
+        }
+        if let Some((last, snapshot)) = &self.last
+            && *last == index {
+            return Ok(snapshot.clone());
+        }
+
+        let entry = &self.entries[index];
+        // Continue the open reader unless it is past the snapshot or a keyframe is closer
+        let reusable = self.cursor.as_ref().is_some_and(|cursor| {
+            cursor.segment == entry.segment && entry.keyframe < cursor.next && cursor.next <= index
+        });
+        if !reusable {
+            let start = &self.entries[entry.keyframe];
+            let source = open_at(&self.segments[start.segment], start.offset)?;
+            self.cursor = Some(Cursor {
+                segment: start.segment,
+                next: entry.keyframe,
+                reader: CaptureReader::starting_at_line(source, start.line),
+            });
+        }
+
+        let cursor = self.cursor.as_mut().expect("cursor opened above");
+        loop {
+            let snapshot = cursor.reader.next().unwrap_or_else(|| {
+                Err(io::Error::new(io::ErrorKind::UnexpectedEof, "capture changed while it was being read"))
+            });
+            let snapshot = match snapshot {
+                Ok(snapshot) => snapshot,
+                Err(e) => {
+                    self.cursor = None;
+                    return Err(e);
+                }
+            };
+            cursor.next += 1;
+            if cursor.next > index {
+                self.last = Some((index, snapshot.clone()));
+                return Ok(snapshot);
+            }
+        }
+    }
+}
+
+/// Parse a file holding a single snapshot or an array of snapshots
+fn load_document(path: &Path) -> io::Result<Vec<MonitorSnapshot>> {
+    let mut content = String::new();
+    open_decompressed(path)?.read_to_string(&mut content)?;
+
+    let mut snapshots = if content.trim_start().starts_with('[') {
+        serde_json::from_str(&content)?
+    } else {
+        let mut reader = CaptureReader::from_reader(Box::new(io::empty()));
+        vec![reader.parse_value(serde_json::from_str(&content)?)?]
+    };
+    snapshots.iter_mut().for_each(MonitorSnapshot::resolve_finding_processes);
+    Ok(snapshots)
+}
+
+/// Reconstruct the latest snapshot taken at or before `at`, looking through
+/// rotated segments too
+pub fn snapshot_at(path: &Path, at: DateTime<Local>) -> io::Result<Option<MonitorSnapshot>> {
+    let mut found = None;
+    // Every segment starts with a keyframe, so each one is read on its own
+    for segment in segments(path) {
+        for snapshot in CaptureReader::open(&segment)? {
+            let snapshot = snapshot?;
+            if snapshot.timestamp > at {
+                return Ok(found);
+            }
+            found = Some(snapshot);
+        }
+    }
+    Ok(found)
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::fixtures::{process, snapshot};
+
+    /// An empty directory for one test's files
+    fn scratch_dir(test: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("rprocmon-{}-{}", std::process::id(), test));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).expect("scratch directory");
+        dir
+    }
+
+    fn options(path: PathBuf, gzip: bool, rotate_size: Option<u64>) -> CaptureOptions {
+        CaptureOptions { path, rotate_size, rotate_interval: None, gzip, keyframe_interval: Some(10) }
+    }
+
+    fn write(options: CaptureOptions, seconds: &[i64]) -> CaptureWriter {
+        let mut writer = CaptureWriter::create(options).expect("create capture");
+        for &second in seconds {
+            let processes = vec![process(1, json!({})), process(100 + second as u32, json!({}))];
+            writer.write_snapshot(&snapshot(second, processes)).expect("write snapshot");
+        }
+        writer
+    }
+
+    #[test]
+    fn gzip_segments_without_a_trailer_are_read_up_to_the_last_record() {
+        let dir = scratch_dir("no-trailer");
+        let path = dir.join("capture.ndjson.gz");
+        // Every record is flushed, but the trailer is only written on drop, as if killed
+        std::mem::forget(write(options(path.clone(), true, None), &[0, 1, 2]));
+
+        let mut index = CaptureIndex::open(&path).expect("index capture");
+        assert_eq!(index.len(), 3);
+        assert_eq!(index.get(2).expect("last snapshot").processes[1].pid, 102);
+        let at = index.timestamp(2) + chrono::Duration::seconds(10);
+        assert_eq!(snapshot_at(&path, at).expect("read capture").map(|s| s.timestamp), Some(index.timestamp(2)));
+        std::fs::remove_dir_all(dir).ok();
+    }
+
+    #[test]
+    fn new_captures_remove_stale_segments() {
+        let dir = scratch_dir("stale-segments");
+        let path = dir.join("capture.ndjson");
+        write(options(path.clone(), false, Some(1)), &[0, 1, 2]).finish().expect("finish capture");
+        assert_eq!(segments(&path).len(), 3);
+
+        write(options(path.clone(), false, Some(1)), &[10]).finish().expect("finish capture");
+        assert_eq!(segments(&path).len(), 1);
+        let mut index = CaptureIndex::open(&path).expect("index capture");
+        assert_eq!(index.len(), 1);
+        assert_eq!(index.get(0).expect("snapshot").processes[1].pid, 110);
+        std::fs::remove_dir_all(dir).ok();
+    }
+}
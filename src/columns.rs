@@ -0,0 +1,384 @@
+//! Process list columns and sort order, selectable with `--columns`/`--sort` or
+//! the display profile in the configuration file.
+
+use std::cmp::Ordering;
+use std::fmt;
+use std::str::FromStr;
+
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+
+use crate::types::{IoRates, ProcessInfo};
+use crate::utils::{format_duration, format_size};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Column {
+    Pid,
+    Name,
+    Cpu,
+    Memory,
+    Ppid,
+    User,
+    Group,
+    Status,
+    Uptime,
+    Threads,
+    // Kilobytes read and written during the last refresh interval, a count rather than a rate
+    Io,
+    // Storage read and write rates from /proc/<pid>/io
+    Read,
+    Write,
+    Exe,
+    Cmd,
+    // Sparklines of recent CPU and resident memory, drawn from the interface's history
+    CpuHistory,
+    MemHistory,
+}
+
+impl Column {
+    pub const ALL: &[Column] = &[
+        Column::Pid, Column::Name, Column::Cpu, Column::Memory, Column::Ppid, Column::User, Column::Group,
+        Column::Status, Column::Uptime, Column::Threads, Column::Io, Column::Read, Column::Write,
+        Column::Exe, Column::Cmd, Column::CpuHistory, Column::MemHistory,
+    ];
+
+    /// Name used in `--columns` and the config file
+    pub fn id(self) -> &'static str {
+        match self {
+            Column::Pid => "pid",
+            Column::Name => "name",
+            Column::Cpu => "cpu",
+            Column::Memory => "mem",
+            Column::Ppid => "ppid",
+            Column::User => "user",
+            Column::Group => "group",
+            Column::Status => "status",
+            Column::Uptime => "uptime",
+            Column::Threads => "threads",
+            Column::Io => "io",
+            Column::Read => "read",
+            Column::Write => "write",
+            Column::Exe => "exe",
+            Column::Cmd => "cmd",
+            Column::CpuHistory => "cpu-history",
+            Column::MemHistory => "mem-history",
+        }
+    }
+
+    pub fn header(self) -> &'static str {
+        match self {
+            Column::Pid => "PID",
+            Column::Name => "NAME",
+            Column::Cpu => "CPU%",
+            Column::Memory => "MEMORY(KB)",
+            Column::Ppid => "PPID",
+            Column::User => "USER",
+            Column::Group => "GROUP",
+            Column::Status => "STATUS",
+            Column::Uptime => "UPTIME",
+            Column::Threads => "THR",
+            Column::Io => "R/W KB/REFRESH",
+            Column::Read => "READ/s",
+            Column::Write => "WRITE/s",
+            Column::Exe => "EXE",
+            Column::Cmd => "CMD",
+            Column::CpuHistory => "CPU HISTORY",
+            Column::MemHistory => "MEM HISTORY",
+        }
+    }
+
+    pub fn default_width(self) -> usize {
+        match self {
+            Column::Pid | Column::Ppid | Column::Cpu => 8,
+            Column::Name => 25,
+            Column::Memory => 12,
+            Column::User | Column::Group | Column::Status | Column::Uptime => 10,
+            Column::Threads => 5,
+            Column::Read | Column::Write => 10,
+            Column::CpuHistory | Column::MemHistory => 12,
+            Column::Io => 15,
+            Column::Exe => 40,
+            Column::Cmd => 60,
+        }
+    }
+
+    /// The cell text for `process`; `now` is the snapshot time in epoch seconds
+    pub fn value(self, process: &ProcessInfo, now: u64) -> String {
+        match self {
+            Column::Pid => process.pid.to_string(),
+            Column::Name => process.name.clone(),
+            Column::Cpu => format!("{:.1}", process.cpu_usage),
+            Column::Memory => (process.memory / 1024).to_string(),
+            Column::Ppid => process.parent_pid.map_or("-".to_string(), |p| p.to_string()),
+            Column::User => display_id(process.user_name.as_deref(), process.user_id),
+            Column::Group => display_id(process.group_name.as_deref(), process.group_id),
+            Column::Status => process.status.clone(),
+            Column::Uptime => format_duration(now.saturating_sub(process.start_time)),
+            Column::Threads => match process.threads {
+                0 => "-".to_string(),
+                n => n.to_string(),
+            },
+            Column::Io => io_per_refresh(process).map_or("-".to_string(), |(read, written)| {
+                format!("{:.0}/{:.0}", read / 1024.0, written / 1024.0)
+            }),
+            Column::Read => process.io_rates.map_or("-".to_string(), |r| format_size(r.read_bytes)),
+            Column::Write => process.io_rates.map_or("-".to_string(), |r| format_size(r.write_bytes)),
+            Column::Exe => format!("{}{}",
+                                   process.exe_path.as_deref().unwrap_or("-"),
+                                   if process.exe_deleted { " (deleted)" } else { "" }),
+            Column::Cmd => process.cmd.join(" "),
+            // A single snapshot has no history; the process list draws these itself
+            Column::CpuHistory | Column::MemHistory => String::new(),
+        }
+    }
+}
+
+/// Resolved name, or the numeric ID when the name is unknown
+pub fn display_id(name: Option<&str>, id: Option<u32>) -> String {
+    match (name, id) {
+        (Some(name), _) => name.to_string(),
+        (None, Some(id)) => id.to_string(),
+        (None, None) => "-".to_string(),
+    }
+}
+
+impl fmt::Display for Column {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.id())
+    }
+}
+
+impl FromStr for Column {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim().to_lowercase();
+        // Accept the header names too, e.g. `memory`
+        Column::ALL.iter()
+            .copied()
+            .find(|c| c.id() == s || c.header().to_lowercase().trim_end_matches("(kb)") == s)
+            .ok_or_else(|| {
+                let known: Vec<&str> = Column::ALL.iter().map(|c| c.id()).collect();
+                format!("unknown column '{}' (expected one of: {})", s, known.join(", "))
+            })
+    }
+}
+
+/// A column and its width, written `name` or `name:width`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct ColumnSpec {
+    pub column: Column,
+    pub width: usize,
+}
+
+impl ColumnSpec {
+    pub fn defaults() -> Vec<ColumnSpec> {
+        [Column::Pid, Column::Name, Column::Cpu, Column::Memory, Column::Ppid,
+         Column::User, Column::Status, Column::Uptime, Column::CpuHistory, Column::MemHistory]
+            .into_iter()
+            .map(ColumnSpec::from)
+            .collect()
+    }
+}
+
+impl From<Column> for ColumnSpec {
+    fn from(column: Column) -> Self {
+        Self { column, width: column.default_width() }
+    }
+}
+
+impl FromStr for ColumnSpec {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (name, width) = match s.split_once(':') {
+            Some((name, width)) => (name, Some(width)),
+            None => (s, None),
+        };
+        let column: Column = name.parse()?;
+        let width = match width {
+            Some(w) => w.trim().parse::<usize>()
+                .ok()
+                .filter(|w| *w > 0)
+                .ok_or_else(|| format!("invalid width '{}' for column {}", w, column))?,
+            None => column.default_width(),
+        };
+        Ok(Self { column, width })
+    }
+}
+
+impl TryFrom<String> for ColumnSpec {
+    type Error = String;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+impl From<ColumnSpec> for String {
+    fn from(spec: ColumnSpec) -> Self {
+        if spec.width == spec.column.default_width() {
+            spec.column.to_string()
+        } else {
+            format!("{}:{}", spec.column, spec.width)
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SortKey {
+    #[default]
+    Cpu,
+    #[value(alias = "memory")]
+    Mem,
+    Pid,
+    #[value(alias = "start-time")]
+    Start,
+    Name,
+    User,
+    // Bytes read and written during the last refresh interval
+    Io,
+    // Rates from /proc/<pid>/io
+    Read,
+    Write,
+}
+
+impl SortKey {
+    pub const ALL: &[SortKey] = &[
+        SortKey::Cpu, SortKey::Mem, SortKey::Pid, SortKey::Start, SortKey::Name, SortKey::User, SortKey::Io,
+        SortKey::Read, SortKey::Write,
+    ];
+
+    /// Column whose header carries the sort indicator
+    pub fn column(self) -> Column {
+        match self {
+            SortKey::Cpu => Column::Cpu,
+            SortKey::Mem => Column::Memory,
+            SortKey::Pid => Column::Pid,
+            SortKey::Start => Column::Uptime,
+            SortKey::Name => Column::Name,
+            SortKey::User => Column::User,
+            SortKey::Io => Column::Io,
+            SortKey::Read => Column::Read,
+            SortKey::Write => Column::Write,
+        }
+    }
+
+    /// Usage figures and start times read best largest (newest) first, identifiers smallest first
+    fn descending_by_default(self) -> bool {
+        matches!(self, SortKey::Cpu | SortKey::Mem | SortKey::Start | SortKey::Io | SortKey::Read | SortKey::Write)
+    }
+
+    fn compare(self, a: &ProcessInfo, b: &ProcessInfo) -> Ordering {
+        match self {
+            SortKey::Cpu => a.cpu_usage.total_cmp(&b.cpu_usage),
+            SortKey::Mem => a.memory.cmp(&b.memory),
+            SortKey::Pid => a.pid.cmp(&b.pid),
+            SortKey::Start => a.start_time.cmp(&b.start_time).then(a.start_ticks.cmp(&b.start_ticks)),
+            SortKey::Name => a.name.to_lowercase().cmp(&b.name.to_lowercase()),
+            SortKey::User => (&a.user_name, a.user_id).cmp(&(&b.user_name, b.user_id)),
+            // Processes without rates sort below idle ones
+            SortKey::Io => {
+                let total = |p| io_per_refresh(p).map_or(-1.0, |(read, written)| read + written);
+                total(a).total_cmp(&total(b))
+            }
+            SortKey::Read => rate(a, |r| r.read_bytes).total_cmp(&rate(b, |r| r.read_bytes)),
+            SortKey::Write => rate(a, |r| r.write_bytes).total_cmp(&rate(b, |r| r.write_bytes)),
+        }
+    }
+
+    pub fn next(self) -> SortKey {
+        let i = SortKey::ALL.iter().position(|k| *k == self).unwrap_or(0);
+        SortKey::ALL[(i + 1) % SortKey::ALL.len()]
+    }
+}
+
+fn rate(process: &ProcessInfo, field: impl Fn(&IoRates) -> f64) -> f64 {
+    process.io_rates.as_ref().map_or(-1.0, field)
+}
+
+impl fmt::Display for SortKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let value = self.to_possible_value().expect("no skipped variants");
+        f.write_str(value.get_name())
+    }
+}
+
+/// Sort key and direction of the process list
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SortOrder {
+    pub key: SortKey,
+    // Flip the key's natural direction
+    pub reverse: bool,
+}
+
+impl SortOrder {
+    pub fn compare(&self, a: &ProcessInfo, b: &ProcessInfo) -> Ordering {
+        let ordering = self.key.compare(a, b);
+        let ordering = if self.key.descending_by_default() != self.reverse { ordering.reverse() } else { ordering };
+        // PID keeps equal rows from jumping around between refreshes
+        ordering.then(a.pid.cmp(&b.pid))
+    }
+
+    /// Arrow shown next to the sort key: down for descending, up for ascending
+    pub fn indicator(&self) -> &'static str {
+        if self.key.descending_by_default() != self.reverse { "▼" } else { "▲" }
+    }
+}
+
+/// Columns and sort order of the process list, stored in the configuration file
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct DisplayProfile {
+    #[serde(default = "ColumnSpec::defaults")]
+    pub columns: Vec<ColumnSpec>,
+    #[serde(default)]
+    pub sort: SortOrder,
+}
+
+impl Default for DisplayProfile {
+    fn default() -> Self {
+        Self {
+            columns: ColumnSpec::defaults(),
+            sort: SortOrder::default(),
+        }
+    }
+}
+
+/// Bytes read from and written to storage since the previous refresh
+fn io_per_refresh(process: &ProcessInfo) -> Option<(f64, f64)> {
+    process.io_rates.as_ref().map(|r| (r.read_bytes * r.seconds, r.write_bytes * r.seconds))
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::fixtures::process;
+    use crate::types::IoCounters;
+
+    /// A process that read `read` and wrote `written` bytes of storage over the last two seconds
+    fn with_io(pid: u32, read: u64, written: u64) -> ProcessInfo {
+        let earlier = IoCounters::default();
+        let now = IoCounters { read_bytes: read, write_bytes: written, ..earlier };
+        process(pid, json!({"io": now, "io_rates": now.rates_since(&earlier, 2.0)}))
+    }
+
+    #[test]
+    fn io_counts_bytes_since_the_previous_refresh() {
+        assert_eq!(Column::Io.value(&with_io(300, 4096, 10240), 0), "4/10");
+        assert_eq!(Column::Read.value(&with_io(300, 4096, 10240), 0), format_size(2048.0));
+        assert_eq!(Column::Io.value(&process(300, json!({})), 0), "-");
+    }
+
+    #[test]
+    fn io_sorts_unreadable_processes_below_idle_ones() {
+        let (busy, idle, unknown) = (with_io(1, 4096, 4096), with_io(2, 0, 0), process(3, json!({})));
+        assert_eq!(SortKey::Io.compare(&busy, &idle), Ordering::Greater);
+        assert_eq!(SortKey::Io.compare(&idle, &unknown), Ordering::Greater);
+    }
+}
@@ -0,0 +1,558 @@
+//! The configuration file and where it is found.
+//!
+//! Settings are layered: built-in defaults, then the system-wide file in
+//! `/etc/rprocmon/`, then the user's file (`--config`, or the one in
+//! `$XDG_CONFIG_HOME/rprocmon/`, falling back to `~/.config/rprocmon/`). Settings
+//! tables such as `monitor` are merged key by key, while collections (hidden names
+//! and PIDs, renames, rules and redaction profiles) and anything else are replaced
+//! whole by the later layer, so a layer can remove entries of the one below. A
+//! layer clears settings below it with null, or in TOML, which has no null, by
+//! listing them in `unset`, e.g. `unset = ["monitor.filter"]`. Only the user's file
+//! is ever written, and it only records what differs from the layers below it.
+//!
+//! Files are TOML, YAML or JSON, chosen by extension (`.toml`, `.yaml`/`.yml`,
+//! anything else is JSON). In a directory the first of `config.toml`,
+//! `config.yaml`, `config.yml` and `config.json` that exists is used.
+
+use std::collections::HashMap;
+use std::error::Error;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+use crate::args::MonitorArgs;
+use crate::columns::DisplayProfile;
+use crate::filter::Filter;
+use crate::redact::RedactionProfile;
+use crate::rules::Rule;
+
+pub const SYSTEM_CONFIG_DIR: &str = "/etc/rprocmon";
+
+/// Top-level keys a layer replaces whole rather than merging into the layer below
+const COLLECTIONS: &[&str] = &["hidden_processes", "renamed_processes", "hidden_pids", "rules", "redaction"];
+
+/// Candidate file names in a configuration directory, in order of preference
+const CONFIG_FILES: &[&str] = &["config.toml", "config.yaml", "config.yml", "config.json"];
+
+/// Everything the configuration file can hold
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    // Processes to hide by name substring; turned into rules on loading
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub hidden_processes: Vec<String>,
+    // Process name mappings (original_name -> display_name)
+    pub renamed_processes: HashMap<String, String>,
+    // Processes to hide by PID
+    pub hidden_pids: Vec<u32>,
+    // Hide and rename rules with explicit match modes
+    pub rules: Vec<Rule>,
+    // Columns and sort order of the process list
+    pub display: DisplayProfile,
+    pub monitor: MonitorSettings,
+    pub output: OutputSettings,
+    pub thresholds: Thresholds,
+    // Named redaction profiles for exported snapshots
+    pub redaction: HashMap<String, RedactionProfile>,
+    // Settings of lower layers to clear, such as "monitor.filter"; turned into nulls on reading
+    #[serde(skip_serializing)]
+    unset: Vec<String>,
+}
+
+/// Defaults for the live monitor options
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct MonitorSettings {
+    // Seconds between refreshes
+    pub interval: u64,
+    // Recent snapshots kept in memory
+    pub history: usize,
+    pub filter: Option<Filter>,
+    pub user: Option<String>,
+    pub network: bool,
+    pub alert: bool,
+    pub verbose: bool,
+    pub tree: bool,
+    // Keep filtered processes in the list and in snapshots, marked with the reason
+    pub show_filtered: bool,
+    // Seconds between scans for processes hidden from /proc; no scan when unset
+    pub hidden_scan: Option<u64>,
+}
+
+impl Default for MonitorSettings {
+    fn default() -> Self {
+        Self {
+            interval: 2,
+            history: 300,
+            filter: None,
+            user: None,
+            network: false,
+            alert: false,
+            verbose: false,
+            tree: false,
+            show_filtered: false,
+            hidden_scan: None,
+        }
+    }
+}
+
+/// Where and how snapshots are streamed, see `--output`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct OutputSettings {
+    pub path: Option<String>,
+    // Megabytes
+    pub rotate_size: Option<u64>,
+    // Seconds
+    pub rotate_interval: Option<u64>,
+    pub gzip: bool,
+    pub delta: bool,
+    pub keyframe_interval: u32,
+    // Redaction profile applied to streamed and saved snapshots
+    pub redact: Option<String>,
+}
+
+impl Default for OutputSettings {
+    fn default() -> Self {
+        Self {
+            path: None,
+            rotate_size: None,
+            rotate_interval: None,
+            gzip: false,
+            delta: false,
+            keyframe_interval: 60,
+            redact: None,
+        }
+    }
+}
+
+/// CPU usage (percent) at which process rows are highlighted, and pressure
+/// (percent of the last 10 seconds some task was stalled) above which a finding is raised
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Thresholds {
+    pub cpu_warning: f32,
+    pub cpu_critical: f32,
+    pub cpu_pressure: Option<f64>,
+    pub memory_pressure: Option<f64>,
+    pub io_pressure: Option<f64>,
+}
+
+impl Default for Thresholds {
+    fn default() -> Self {
+        Self {
+            cpu_warning: 25.0,
+            cpu_critical: 50.0,
+            cpu_pressure: None,
+            memory_pressure: None,
+            io_pressure: None,
+        }
+    }
+}
+
+impl Config {
+    /// Deserialize merged layers, with the hidden names already turned into rules
+    fn from_value(value: Value) -> Result<Self, serde_json::Error> {
+        let mut config: Config = serde_json::from_value(value)?;
+        config.convert_hidden_names();
+        Ok(config)
+    }
+
+    /// Turn the hidden names into the equivalent rules, ahead of the other rules of
+    /// priority 0, so one ordered list decides what is hidden
+    pub fn convert_hidden_names(&mut self) {
+        let position = self.rules.iter().position(|r| r.priority <= 0).unwrap_or(self.rules.len());
+        let names = std::mem::take(&mut self.hidden_processes);
+        let legacy: Vec<Rule> = names.iter()
+            .map(|name| Rule::hidden_name(name))
+            .filter(|rule| !self.rules.iter().any(|r| r.to_string() == rule.to_string()))
+            .collect();
+        self.rules.splice(position..position, legacy);
+    }
+
+    /// The named redaction profile; "default" is built in unless the configuration overrides it
+    pub fn redaction_profile(&self, name: &str) -> Result<RedactionProfile, String> {
+        match self.redaction.get(name) {
+            Some(profile) => Ok(profile.clone()),
+            None if name == "default" => Ok(RedactionProfile::default()),
+            None => {
+                let mut known: Vec<&str> = self.redaction.keys().map(String::as_str).collect();
+                known.sort_unstable();
+                known.insert(0, "default");
+                known.dedup();
+                Err(format!("unknown redaction profile '{}' (expected one of: {})", name, known.join(", ")))
+            }
+        }
+    }
+
+    /// Command-line options take precedence over the configuration files
+    pub fn apply_args(&mut self, args: MonitorArgs) {
+        let monitor = &mut self.monitor;
+        if let Some(interval) = args.interval {
+            monitor.interval = interval;
+        }
+        if let Some(history) = args.history {
+            monitor.history = history;
+        }
+        if args.filter.is_some() {
+            monitor.filter = args.filter;
+        }
+        if args.user.is_some() {
+            monitor.user = args.user;
+        }
+        monitor.network |= args.network;
+        monitor.alert |= args.alert;
+        monitor.verbose |= args.verbose;
+        monitor.tree |= args.tree;
+        monitor.show_filtered |= args.show_filtered;
+        if args.hidden_scan.is_some() {
+            monitor.hidden_scan = args.hidden_scan;
+        }
+
+        let output = &mut self.output;
+        if args.output.is_some() {
+            output.path = args.output;
+        }
+        if args.rotate_size.is_some() {
+            output.rotate_size = args.rotate_size;
+        }
+        if args.rotate_interval.is_some() {
+            output.rotate_interval = args.rotate_interval;
+        }
+        output.gzip |= args.gzip;
+        output.delta |= args.delta;
+        if let Some(keyframe_interval) = args.keyframe_interval {
+            output.keyframe_interval = keyframe_interval;
+        }
+        if args.redact.is_some() {
+            output.redact = args.redact;
+        }
+
+        let display = &mut self.display;
+        if !args.columns.is_empty() {
+            display.columns = args.columns;
+        }
+        if let Some(key) = args.sort {
+            display.sort.key = key;
+        }
+        display.sort.reverse |= args.reverse;
+    }
+}
+
+/// Serialization format of a configuration file
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConfigFormat {
+    Json,
+    Toml,
+    Yaml,
+}
+
+impl ConfigFormat {
+    pub fn from_path(path: &Path) -> Self {
+        match path.extension().and_then(|e| e.to_str()).map(str::to_lowercase).as_deref() {
+            Some("toml") => ConfigFormat::Toml,
+            Some("yaml" | "yml") => ConfigFormat::Yaml,
+            _ => ConfigFormat::Json,
+        }
+    }
+
+    /// Deserialize `content`, describing any error with the line it points at
+    pub fn parse<T: DeserializeOwned>(self, content: &str) -> Result<T, String> {
+        match self {
+            ConfigFormat::Json => serde_json::from_str(content).map_err(|e| {
+                let message = e.to_string();
+                let suffix = format!(" at line {} column {}", e.line(), e.column());
+                located(content, message.strip_suffix(&suffix).unwrap_or(&message), e.line(), e.column())
+            }),
+            ConfigFormat::Toml => toml::from_str(content).map_err(|e| match e.span() {
+                Some(span) => {
+                    let before = &content[..span.start.min(content.len())];
+                    let line = before.matches('\n').count() + 1;
+                    let column = before.len() - before.rfind('\n').map_or(0, |i| i + 1) + 1;
+                    located(content, e.message(), line, column)
+                }
+                None => e.message().to_string(),
+            }),
+            ConfigFormat::Yaml => serde_yaml_ng::from_str(content).map_err(|e| match e.location() {
+                Some(location) => {
+                    let message = e.to_string();
+                    let suffix = format!(" at line {} column {}", location.line(), location.column());
+                    located(content, message.strip_suffix(&suffix).unwrap_or(&message),
+                            location.line(), location.column())
+                }
+                None => e.to_string(),
+            }),
+        }
+    }
+
+    pub fn to_string<T: Serialize>(self, value: &T) -> Result<String, Box<dyn Error>> {
+        Ok(match self {
+            ConfigFormat::Json => serde_json::to_string_pretty(value)?,
+            ConfigFormat::Toml => {
+                // TOML has no null; a missing key means the same thing here
+                let mut value = serde_json::to_value(value)?;
+                drop_nulls(&mut value);
+                toml::to_string_pretty(&value)?
+            }
+            ConfigFormat::Yaml => serde_yaml_ng::to_string(value)?,
+        })
+    }
+}
+
+/// `message` followed by the offending line with a caret under `column` (both 1-based)
+fn located(content: &str, message: &str, line: usize, column: usize) -> String {
+    let Some(text) = content.lines().nth(line.saturating_sub(1)) else {
+        return format!("{} (line {})", message, line);
+    };
+    let gutter = " ".repeat(line.to_string().len());
+    let indent: String = text.chars()
+        .take(column.saturating_sub(1))
+        .map(|c| if c == '\t' { '\t' } else { ' ' })
+        .collect();
+    format!("line {}, column {}: {}\n{} |\n{} | {}\n{} | {}^",
+            line, column, message, gutter, line, text, gutter, indent)
+}
+
+fn drop_nulls(value: &mut Value) {
+    match value {
+        Value::Object(map) => {
+            map.retain(|_, v| !v.is_null());
+            map.values_mut().for_each(drop_nulls);
+        }
+        Value::Array(items) => items.iter_mut().for_each(drop_nulls),
+        _ => {}
+    }
+}
+
+/// Read and validate one configuration file on its own, without the layers
+pub fn read_config(path: &Path) -> Result<Config, Box<dyn Error>> {
+    let content = fs::read_to_string(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+    Ok(Config::from_value(parse_layer(path, &content)?)?)
+}
+
+/// Write the whole of `config` in the format the extension of `path` calls for
+pub fn write_config(path: &Path, config: &Config) -> Result<(), Box<dyn Error>> {
+    fs::write(path, ConfigFormat::from_path(path).to_string(config)?)?;
+    Ok(())
+}
+
+/// The user's configuration file and the layers underneath it
+pub struct ConfigStore {
+    // None when neither `--config` nor a home directory is available
+    path: Option<PathBuf>,
+    system_path: PathBuf,
+    // The settings each file holds, as last read or written
+    system: Option<Value>,
+    user: Option<Value>,
+    // Built-in defaults merged with the system-wide file
+    base: Value,
+}
+
+impl ConfigStore {
+    /// Use `path` as the user's file, or the default location when it is None
+    pub fn locate(path: Option<&Path>) -> Result<Self, Box<dyn Error>> {
+        let mut base = serde_json::to_value(Config::default())?;
+        let system_path = find_config(Path::new(SYSTEM_CONFIG_DIR));
+        let system = read_layer(&system_path)?;
+        if let Some(layer) = &system {
+            merge(&mut base, layer.clone(), COLLECTIONS);
+            // Normalise e.g. `10` to `10.0` so saving can compare against it
+            let config = Config::from_value(base)?;
+            base = serde_json::to_value(config)?;
+        }
+
+        Ok(Self {
+            path: path.map(Path::to_path_buf).or_else(user_config_path),
+            system_path,
+            system,
+            user: None,
+            base,
+        })
+    }
+
+    pub fn path(&self) -> Option<&Path> {
+        self.path.as_deref()
+    }
+
+    /// The effective configuration; a missing file just means nothing is overridden
+    pub fn load(&mut self) -> Result<Config, Box<dyn Error>> {
+        let mut value = self.base.clone();
+        self.user = match &self.path {
+            Some(path) => read_layer(path)?,
+            None => None,
+        };
+        if let Some(layer) = &self.user {
+            merge(&mut value, layer.clone(), COLLECTIONS);
+        }
+        Ok(Config::from_value(value)?)
+    }
+
+    /// Write the parts of `config` that differ from the system-wide defaults
+    pub fn save(&mut self, config: &Config) -> Result<(), Box<dyn Error>> {
+        let path = self.path.as_ref()
+            .ok_or("no configuration file location (set $HOME or use --config)")?;
+
+        let mut value = serde_json::to_value(config)?;
+        strip_base(&mut value, &self.base, COLLECTIONS);
+        let format = ConfigFormat::from_path(path);
+        let mut written = value.clone();
+        if format == ConfigFormat::Toml {
+            nulls_to_unset(&mut written);
+        }
+        if let Some(dir) = path.parent().filter(|d| !d.as_os_str().is_empty()) {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(path, format.to_string(&written)?)?;
+        self.user = Some(value);
+        Ok(())
+    }
+
+    /// The file setting `key`, a dotted name such as "monitor.filter", or None for a built-in default
+    pub fn source(&self, key: &str) -> Option<&Path> {
+        let sets = |layer: &Option<Value>| {
+            layer.as_ref().is_some_and(|layer| key.split('.').try_fold(layer, |v, k| v.get(k)).is_some())
+        };
+        if sets(&self.user) {
+            self.path()
+        } else if sets(&self.system) {
+            Some(&self.system_path)
+        } else {
+            None
+        }
+    }
+}
+
+/// The existing configuration file in `dir`, or where a new one would go
+fn find_config(dir: &Path) -> PathBuf {
+    CONFIG_FILES.iter()
+        .map(|name| dir.join(name))
+        .find(|path| path.exists())
+        .unwrap_or_else(|| dir.join(CONFIG_FILES[0]))
+}
+
+/// `$XDG_CONFIG_HOME/rprocmon`, or `~/.config/rprocmon` when that is unset
+pub fn user_config_dir() -> Option<PathBuf> {
+    let non_empty = |name| std::env::var_os(name).filter(|v| !v.is_empty()).map(PathBuf::from);
+    let config_home = non_empty("XDG_CONFIG_HOME")
+        .or_else(|| non_empty("HOME").map(|home| home.join(".config")))?;
+    Some(config_home.join("rprocmon"))
+}
+
+/// The user's configuration file in [`user_config_dir`]
+pub fn user_config_path() -> Option<PathBuf> {
+    user_config_dir().map(|dir| find_config(&dir))
+}
+
+/// One layer as a JSON value, so only the keys actually present override lower layers
+fn read_layer(path: &Path) -> Result<Option<Value>, Box<dyn Error>> {
+    let content = match fs::read_to_string(path) {
+        Ok(content) => content,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(format!("{}: {}", path.display(), e).into()),
+    };
+    Ok(Some(parse_layer(path, &content)?))
+}
+
+/// Parse and validate the content of one layer, with its `unset` list turned into nulls
+fn parse_layer(path: &Path, content: &str) -> Result<Value, String> {
+    let format = ConfigFormat::from_path(path);
+    let mut layer: Value = format.parse(content).map_err(|e| format!("{}: {}", path.display(), e))?;
+    // Validate the layer on its own so errors name the file it comes from; only an
+    // invalid file is parsed again, as a `Config`, to find the offending line
+    let unset = match Config::deserialize(&layer) {
+        Ok(config) => config.unset,
+        Err(e) => {
+            let message = format.parse::<Config>(content).err().unwrap_or_else(|| e.to_string());
+            return Err(format!("{}: {}", path.display(), message));
+        }
+    };
+
+    if let Value::Object(map) = &mut layer {
+        map.remove("unset");
+        for key in &unset {
+            let mut sections: Vec<&str> = key.split('.').collect();
+            let name = sections.pop().unwrap_or_default();
+            let mut table = &mut *map;
+            for section in sections {
+                table = table.entry(section)
+                    .or_insert_with(|| Value::Object(Map::new()))
+                    .as_object_mut()
+                    .ok_or_else(|| format!("{}: unset: '{}' is not a table", path.display(), section))?;
+            }
+            table.insert(name.to_string(), Value::Null);
+        }
+    }
+    if let Err(e) = Config::deserialize(&layer) {
+        return Err(format!("{}: unset {}: {}", path.display(), unset.join(", "), e));
+    }
+    Ok(layer)
+}
+
+/// Move the null settings of `value` into an `unset` list, for TOML, which has no null
+fn nulls_to_unset(value: &mut Value) {
+    fn collect(value: &mut Value, prefix: &str, unset: &mut Vec<Value>) {
+        let Value::Object(map) = value else {
+            return;
+        };
+        map.retain(|key, item| {
+            let name = format!("{}{}", prefix, key);
+            if item.is_null() {
+                unset.push(Value::String(name));
+                return false;
+            }
+            collect(item, &format!("{}.", name), unset);
+            item.as_object().is_none_or(|o| !o.is_empty())
+        });
+    }
+
+    let Value::Object(map) = value else {
+        return;
+    };
+    let mut unset = Vec::new();
+    for (key, item) in map.iter_mut().filter(|(key, _)| !COLLECTIONS.contains(&key.as_str())) {
+        collect(item, &format!("{}.", key), &mut unset);
+    }
+    map.retain(|key, item| COLLECTIONS.contains(&key.as_str()) || item.as_object().is_none_or(|o| !o.is_empty()));
+    if !unset.is_empty() {
+        map.insert("unset".to_string(), Value::Array(unset));
+    }
+}
+
+/// Merge `layer` into `value`, recursing into objects present in both except the
+/// `replaced` keys, which the layer replaces whole
+fn merge(value: &mut Value, layer: Value, replaced: &[&str]) {
+    match (value, layer) {
+        (Value::Object(value), Value::Object(layer)) => {
+            for (key, item) in layer {
+                match value.get_mut(&key) {
+                    Some(existing) if !replaced.contains(&key.as_str()) => merge(existing, item, &[]),
+                    _ => {
+                        value.insert(key, item);
+                    }
+                }
+            }
+        }
+        (value, layer) => *value = layer,
+    }
+}
+
+/// Remove everything from `value` that `base` already provides; the `replaced` keys
+/// are kept whole unless they equal the base
+fn strip_base(value: &mut Value, base: &Value, replaced: &[&str]) {
+    let (Value::Object(map), Value::Object(base)) = (value, base) else {
+        return;
+    };
+    map.retain(|key, item| match base.get(key) {
+        Some(default) if default == item => false,
+        Some(_) if replaced.contains(&key.as_str()) => true,
+        Some(default) => {
+            strip_base(item, default, &[]);
+            // An object left empty only held defaults
+            item.as_object().is_none_or(|o| !o.is_empty())
+        }
+        None => true,
+    });
+}
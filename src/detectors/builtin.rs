@@ -0,0 +1,222 @@
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+
+use super::{process_finding, Detector};
+use crate::types::{Finding, MonitorSnapshot, ProcessInfo, ProcessKey, Severity};
+
+/// Directories that are world-writable and commonly used to stage payloads
+const TEMP_DIRS: &[&str] = &["/tmp/", "/dev/shm/", "/var/tmp/"];
+
+/// Run `check` on every process, skipping those it already flagged in `previous`
+///
+/// A process stays suspicious for as long as it runs; reporting it again on every
+/// refresh would bury new findings.
+fn once_per_process(detector: &dyn Detector, current: &MonitorSnapshot, previous: Option<&MonitorSnapshot>,
+                    check: impl Fn(&ProcessInfo) -> Option<(Severity, String)>) -> Vec<Finding> {
+    let reported: HashSet<ProcessKey> = previous.map_or_else(HashSet::new, |previous| {
+        previous.processes.iter()
+            .filter(|p| !p.is_thread && check(p).is_some())
+            .map(|p| p.key())
+            .collect()
+    });
+
+    current.processes.iter()
+        .filter(|p| !p.is_thread && !reported.contains(&p.key()))
+        .filter_map(|p| {
+            let (severity, message) = check(p)?;
+            Some(process_finding(detector, severity, p, message))
+        })
+        .collect()
+}
+
+/// Processes whose executable lives in a temporary directory
+pub struct TempDirExecutable;
+
+impl Detector for TempDirExecutable {
+    fn name(&self) -> &'static str {
+        "temp-dir-exe"
+    }
+
+    fn evaluate(&self, current: &MonitorSnapshot, previous: Option<&MonitorSnapshot>) -> Vec<Finding> {
+        once_per_process(self, current, previous, |p| {
+            let exe = p.exe_path.as_deref()?;
+            TEMP_DIRS.iter().any(|dir| exe.starts_with(dir))
+                .then(|| (Severity::High, format!("executable runs from temporary directory: {}", exe)))
+        })
+    }
+}
+
+/// Processes whose executable was deleted from disk, or never had one (memfd)
+pub struct DeletedExecutable;
+
+impl Detector for DeletedExecutable {
+    fn name(&self) -> &'static str {
+        "deleted-exe"
+    }
+
+    fn evaluate(&self, current: &MonitorSnapshot, previous: Option<&MonitorSnapshot>) -> Vec<Finding> {
+        once_per_process(self, current, previous, |p| {
+            let exe = p.exe_path.as_deref().unwrap_or("-");
+            if exe.starts_with("/memfd:") {
+                Some((Severity::Critical, format!("fileless execution from {}", exe)))
+            } else if p.exe_deleted {
+                Some((Severity::High, format!("executable deleted from disk: {}", exe)))
+            } else {
+                None
+            }
+        })
+    }
+}
+
+/// Processes whose parent exited since the previous snapshot, leaving them
+/// reparented to init or a subreaper
+pub struct OrphanedProcess;
+
+impl Detector for OrphanedProcess {
+    fn name(&self) -> &'static str {
+        "orphaned"
+    }
+
+    fn evaluate(&self, current: &MonitorSnapshot, previous: Option<&MonitorSnapshot>) -> Vec<Finding> {
+        let Some(previous) = previous else {
+            return Vec::new();
+        };
+
+        let previous_parents: HashMap<ProcessKey, Option<u32>> = previous.processes.iter()
+            .map(|p| (p.key(), p.parent_pid))
+            .collect();
+        let alive: HashSet<u32> = current.processes.iter().map(|p| p.pid).collect();
+
+        current.processes.iter()
+            .filter(|p| !p.is_thread)
+            .filter_map(|p| {
+                let old_parent = (*previous_parents.get(&p.key())?)?;
+                let new_parent = p.parent_pid?;
+                if old_parent == new_parent || alive.contains(&old_parent) {
+                    return None;
+                }
+
+                let adopter = if new_parent == 1 { "init".to_string() } else { format!("PID {}", new_parent) };
+                Some(process_finding(self, Severity::Medium, p,
+                                     format!("parent PID {} exited, reparented to {}", old_parent, adopter)))
+            })
+            .collect()
+    }
+}
+
+/// Processes whose reported name does not match the executable or command line,
+/// a common masquerading trick (e.g. a payload calling itself `kworker`)
+pub struct NameMismatch;
+
+/// The kernel truncates process names (`comm`) to 15 bytes
+const COMM_LEN: usize = 15;
+
+impl Detector for NameMismatch {
+    fn name(&self) -> &'static str {
+        "name-mismatch"
+    }
+
+    fn evaluate(&self, current: &MonitorSnapshot, previous: Option<&MonitorSnapshot>) -> Vec<Finding> {
+        once_per_process(self, current, previous, |p| {
+            if p.name.is_empty() {
+                return None;
+            }
+            // Kernel threads have no executable
+            let exe = p.exe_path.as_deref()?;
+            // Compare the kernel's name, not the one a rename rule shows; loaded
+            // captures only have the latter
+            let original = if p.comm.is_empty() { &p.name } else { &p.comm };
+            let comm: String = original.chars().take(COMM_LEN).collect();
+
+            // The name may come from the exe, a symlink used as argv[0] (sh -> dash)
+            // or a script run by an interpreter (python3 script.py)
+            let matches = std::iter::once(exe)
+                .chain(p.cmd.iter().take(2).map(String::as_str))
+                .filter_map(|path| Path::new(path).file_name())
+                .any(|base| base.to_string_lossy().starts_with(&comm));
+
+            (!matches).then(|| (Severity::Medium, format!("name '{}' does not match executable {}", p.name, exe)))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::fixtures::{process, snapshot, START};
+
+    fn messages(detector: &dyn Detector, current: &MonitorSnapshot, previous: Option<&MonitorSnapshot>) -> Vec<String> {
+        detector.evaluate(current, previous).into_iter().map(|f| f.message).collect()
+    }
+
+    #[test]
+    fn temp_dir_executables_are_reported_once() {
+        let payload = process(300, json!({"exe_path": "/dev/shm/x"}));
+        let first = snapshot(0, vec![process(1, json!({})), payload.clone()]);
+        let second = snapshot(1, vec![process(1, json!({})), payload]);
+
+        let findings = TempDirExecutable.evaluate(&first, None);
+        assert_eq!(findings.len(), 1);
+        assert_eq!(findings[0].process, Some(ProcessKey { pid: 300, start: START as u64 }));
+        assert_eq!(findings[0].severity, Severity::High);
+        assert!(TempDirExecutable.evaluate(&second, Some(&first)).is_empty());
+    }
+
+    #[test]
+    fn reused_pids_are_reported_again() {
+        let first = snapshot(0, vec![process(300, json!({"exe_path": "/tmp/a"}))]);
+        let second = snapshot(5, vec![process(300, json!({"exe_path": "/tmp/a", "start_time": START + 4}))]);
+        assert_eq!(TempDirExecutable.evaluate(&second, Some(&first)).len(), 1);
+    }
+
+    #[test]
+    fn processes_turning_suspicious_are_reported() {
+        let first = snapshot(0, vec![process(300, json!({}))]);
+        let second = snapshot(1, vec![process(300, json!({"exe_deleted": true}))]);
+        assert!(DeletedExecutable.evaluate(&first, None).is_empty());
+        assert_eq!(messages(&DeletedExecutable, &second, Some(&first)), ["executable deleted from disk: /usr/bin/proc300"]);
+        assert!(DeletedExecutable.evaluate(&second, Some(&second)).is_empty());
+    }
+
+    #[test]
+    fn memfd_executables_are_critical() {
+        let current = snapshot(0, vec![
+            process(300, json!({"exe_path": "/memfd:payload (deleted)", "exe_deleted": true})),
+            process(301, json!({"exe_path": "/tmp/t", "is_thread": true})),
+        ]);
+        let findings = DeletedExecutable.evaluate(&current, None);
+        assert_eq!(findings.len(), 1);
+        assert_eq!(findings[0].severity, Severity::Critical);
+        assert!(TempDirExecutable.evaluate(&current, None).is_empty());
+    }
+
+    #[test]
+    fn orphans_are_reported_when_the_parent_exits() {
+        let first = snapshot(0, vec![process(200, json!({})), process(300, json!({"parent_pid": 200}))]);
+        let second = snapshot(1, vec![process(300, json!({"parent_pid": 1}))]);
+        assert_eq!(messages(&OrphanedProcess, &second, Some(&first)), ["parent PID 200 exited, reparented to init"]);
+        assert!(OrphanedProcess.evaluate(&second, Some(&second)).is_empty());
+        assert!(OrphanedProcess.evaluate(&second, None).is_empty());
+    }
+
+    #[test]
+    fn names_may_come_from_the_exe_argv_or_a_script() {
+        let current = snapshot(0, vec![
+            process(300, json!({"name": "sh", "exe_path": "/usr/bin/dash", "cmd": ["/bin/sh", "-c", "true"]})),
+            process(301, json!({"name": "backup.py", "exe_path": "/usr/bin/python3.12", "cmd": ["python3", "/opt/backup.py"]})),
+            process(302, json!({"name": "a-very-long-process-name", "exe_path": "/usr/bin/a-very-long-process-name"})),
+            process(303, json!({"name": "kworker/0:1", "exe_path": serde_json::Value::Null, "cmd": []})),
+        ]);
+        assert!(NameMismatch.evaluate(&current, None).is_empty());
+    }
+
+    #[test]
+    fn masquerading_names_are_reported_once() {
+        let first = snapshot(0, vec![process(300, json!({"name": "kworker/0:2", "exe_path": "/home/u/.x/miner", "cmd": ["kworker/0:2"]}))]);
+        let second = snapshot(1, first.processes.clone());
+        assert_eq!(messages(&NameMismatch, &first, None), ["name 'kworker/0:2' does not match executable /home/u/.x/miner"]);
+        assert!(NameMismatch.evaluate(&second, Some(&first)).is_empty());
+    }
+}
@@ -0,0 +1,323 @@
+//! Cross-view detection of processes hidden from normal enumeration.
+//!
+//! A rootkit usually hides a process by filtering one way of listing processes
+//! (`getdents` on `/proc`), not every way of reaching it. The scan looks at each
+//! PID up to a little past the highest one listed through four views and reports
+//! any PID that some of them see and others do not:
+//!
+//! - the process list sysinfo returned for the snapshot
+//! - a readdir of `/proc`, and of `/proc/<tgid>/task` for threads
+//! - `/proc/<pid>/stat`, looked up directly
+//! - `kill(pid, 0)`
+//!
+//! Processes start and exit while the views are read, so every mismatch is
+//! checked a second time and PIDs that started after the process list was taken
+//! are left out. Probing tens of thousands of PIDs takes a while, so the scan is
+//! opt-in (`--hidden-scan`), runs on its own interval and on a thread of its own,
+//! keeping the monitor responsive.
+
+use std::collections::HashSet;
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+use crate::types::Finding;
+
+#[cfg(target_os = "linux")]
+use linux::{pid_max, proc_restricted, ProcViews};
+
+/// Recorded as the detector of every finding
+const NAME: &str = "hidden-process";
+
+/// PIDs probed past the highest one any view lists, for processes started since
+#[cfg(target_os = "linux")]
+const PID_HEADROOM: u32 = 4096;
+
+pub struct HiddenProcessScan {
+    interval: Duration,
+    last_scan: Option<Instant>,
+    // The scan in progress, if any
+    running: Option<JoinHandle<Vec<Finding>>>,
+    // Findings of the latest finished scan, attached to every snapshot until the next one
+    findings: Vec<Finding>,
+}
+
+impl HiddenProcessScan {
+    pub fn new(interval: Duration) -> Self {
+        Self { interval, last_scan: None, running: None, findings: Vec::new() }
+    }
+
+    /// Findings of the latest finished scan, starting another one in the background
+    /// when the interval has passed
+    ///
+    /// `listed` holds every PID and thread ID in the process list, `listed_at`
+    /// the time since boot in clock ticks when that list was taken.
+    pub fn findings(&mut self, listed: &HashSet<u32>, listed_at: Option<u64>) -> &[Finding] {
+        if let Some(running) = self.running.take_if(|running| running.is_finished()) {
+            // A scan that panicked found nothing; the next one runs on schedule
+            self.findings = running.join().unwrap_or_default();
+        }
+        if self.running.is_none() && self.last_scan.is_none_or(|last| last.elapsed() >= self.interval) {
+            let listed = listed.clone();
+            self.running = Some(thread::spawn(move || scan(&listed, listed_at)));
+            self.last_scan = Some(Instant::now());
+        }
+        &self.findings
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn scan(listed: &HashSet<u32>, listed_at: Option<u64>) -> Vec<Finding> {
+    let restricted = proc_restricted();
+
+    let views = ProcViews::read();
+    // PIDs are handed out in increasing order, so a hidden process is rarely far past
+    // the highest visible one; probing all of pid_max would take seconds
+    let highest = listed.iter().copied().chain(views.highest()).max().unwrap_or(0);
+    let limit = highest.saturating_add(PID_HEADROOM).min(pid_max());
+    let candidates: Vec<u32> = (1..limit)
+        .filter(|&pid| views.mismatch(pid, listed, listed_at, restricted).is_some())
+        .collect();
+
+    // Read the listings again so processes that just started or exited drop out
+    let views = ProcViews::read();
+    candidates.into_iter()
+        .filter_map(|pid| {
+            let mismatch = views.mismatch(pid, listed, listed_at, restricted)?;
+            Some(linux::finding(pid, mismatch))
+        })
+        .collect()
+}
+
+#[cfg(not(target_os = "linux"))]
+fn scan(_listed: &HashSet<u32>, _listed_at: Option<u64>) -> Vec<Finding> {
+    Vec::new()
+}
+
+#[cfg(target_os = "linux")]
+mod linux {
+    use std::collections::{HashMap, HashSet};
+    use std::fs;
+    use std::io;
+    use std::path::Path;
+
+    use super::NAME;
+    use crate::procfs;
+    use crate::types::{Finding, ProcessKey, Severity};
+
+    /// Upper bound of `pid_max` on 64-bit kernels, used when it cannot be read
+    const PID_MAX_LIMIT: u32 = 4_194_304;
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub(super) enum View {
+        List,
+        Readdir,
+        Stat,
+        Kill,
+    }
+
+    /// The views that saw a PID and those that did not
+    #[derive(Debug)]
+    pub(super) struct Mismatch {
+        pub(super) seen: Vec<View>,
+        pub(super) missing: Vec<View>,
+    }
+
+    /// The `/proc` directory listings, read once per pass
+    pub(super) struct ProcViews {
+        pub(super) entries: HashSet<u32>,
+        // Thread ID -> thread group ID, from every listed `/proc/<tgid>/task`
+        pub(super) tasks: HashMap<u32, u32>,
+    }
+
+    impl ProcViews {
+        pub(super) fn read() -> Self {
+            let entries: HashSet<u32> = numeric_entries("/proc").collect();
+            let tasks = entries.iter()
+                .flat_map(|&tgid| numeric_entries(format!("/proc/{}/task", tgid))
+                    .map(move |tid| (tid, tgid)))
+                .collect();
+            Self { entries, tasks }
+        }
+
+        /// The highest PID or thread ID in the listings
+        pub(super) fn highest(&self) -> Option<u32> {
+            self.entries.iter().chain(self.tasks.keys()).copied().max()
+        }
+
+        /// How the views disagree about `pid`, if they do and it is still running
+        pub(super) fn mismatch(&self, pid: u32, listed: &HashSet<u32>, listed_at: Option<u64>,
+                               restricted: bool) -> Option<Mismatch> {
+            let readdir = self.entries.contains(&pid) || self.tasks.contains_key(&pid);
+            let stat = Path::new(&format!("/proc/{}/stat", pid)).exists();
+            let kill = signal_probe(pid);
+            if !readdir && !stat && !kill {
+                // Never existed, or exited since the process list was taken
+                return None;
+            }
+
+            let mut views = vec![(View::Readdir, readdir), (View::Stat, stat)];
+            // With hidepid, /proc hides other users' processes that kill() still reaches
+            if !restricted {
+                views.push((View::Kill, kill));
+            }
+            let list = listed.contains(&pid);
+            let started_later = || procfs::start_ticks(pid).zip(listed_at).is_some_and(|(start, at)| start >= at);
+            if list || !started_later() {
+                views.push((View::List, list));
+            }
+
+            if views.iter().all(|&(_, seen)| seen) || views.iter().all(|&(_, seen)| !seen) {
+                return None;
+            }
+            let (seen, missing): (Vec<_>, Vec<_>) = views.into_iter().partition(|&(_, seen)| seen);
+            Some(Mismatch {
+                seen: seen.into_iter().map(|(view, _)| view).collect(),
+                missing: missing.into_iter().map(|(view, _)| view).collect(),
+            })
+        }
+    }
+
+    pub(super) fn finding(pid: u32, mismatch: Mismatch) -> Finding {
+        let name = fs::read_to_string(format!("/proc/{}/comm", pid)).ok()
+            .map(|comm| comm.trim_end().to_string());
+        let tgid = thread_group(pid).filter(|&tgid| tgid != pid);
+        let describe = |views: &[View]| {
+            let names: Vec<String> = views.iter()
+                .map(|view| match view {
+                    View::List => "the process list".to_string(),
+                    View::Readdir => match tgid {
+                        Some(tgid) => format!("/proc/{}/task", tgid),
+                        None => "the /proc listing".to_string(),
+                    },
+                    View::Stat => format!("/proc/{}/stat", pid),
+                    View::Kill => "kill(2)".to_string(),
+                })
+                .collect();
+            match names.split_last() {
+                Some((last, rest)) if !rest.is_empty() => format!("{} and {}", rest.join(", "), last),
+                _ => names.join(""),
+            }
+        };
+
+        Finding {
+            detector: NAME.to_string(),
+            severity: Severity::High,
+            process: procfs::start_ticks(pid).map(|start| ProcessKey { pid, start }),
+            message: format!("{} {}{} is missing from {} but seen by {}",
+                             if tgid.is_some() { "thread" } else { "PID" },
+                             pid,
+                             name.as_ref().map_or(String::new(), |name| format!(" '{}'", name)),
+                             describe(&mismatch.missing),
+                             describe(&mismatch.seen)),
+            process_name: name,
+        }
+    }
+
+    /// Whether `kill(pid, 0)` finds a process, even one we may not signal
+    fn signal_probe(pid: u32) -> bool {
+        // SAFETY: signal 0 sends nothing, it only checks that the process exists
+        let result = unsafe { libc::kill(pid as libc::pid_t, 0) };
+        result == 0 || io::Error::last_os_error().raw_os_error() == Some(libc::EPERM)
+    }
+
+    fn numeric_entries(dir: impl AsRef<Path>) -> impl Iterator<Item = u32> {
+        fs::read_dir(dir).into_iter()
+            .flatten()
+            .flatten()
+            .filter_map(|entry| entry.file_name().to_str()?.parse().ok())
+    }
+
+    /// The `Tgid:` line of `/proc/<pid>/status`
+    fn thread_group(pid: u32) -> Option<u32> {
+        let status = fs::read_to_string(format!("/proc/{}/status", pid)).ok()?;
+        status.lines().find_map(|l| l.strip_prefix("Tgid:"))?.trim().parse().ok()
+    }
+
+    /// PIDs are allocated below this
+    pub(super) fn pid_max() -> u32 {
+        fs::read_to_string("/proc/sys/kernel/pid_max").ok()
+            .and_then(|max| max.trim().parse().ok())
+            .unwrap_or(PID_MAX_LIMIT)
+    }
+
+    /// Whether `/proc` is mounted with `hidepid` and we are not root
+    pub(super) fn proc_restricted() -> bool {
+        // SAFETY: geteuid has no preconditions and cannot fail
+        if unsafe { libc::geteuid() } == 0 {
+            return false;
+        }
+        let mounts = fs::read_to_string("/proc/mounts").unwrap_or_default();
+        mounts.lines()
+            .map(|line| line.split_whitespace().collect::<Vec<_>>())
+            .filter(|fields| fields.get(1) == Some(&"/proc") && fields.get(2) == Some(&"proc"))
+            .any(|fields| fields.get(3).is_some_and(|options| options.split(',').any(|option| {
+                option.strip_prefix("hidepid=").is_some_and(|value| !matches!(value, "0" | "off"))
+            })))
+    }
+}
+
+#[cfg(all(test, target_os = "linux"))]
+mod tests {
+    use super::linux::View;
+    use super::*;
+
+    fn own_views() -> (u32, ProcViews) {
+        (std::process::id(), ProcViews::read())
+    }
+
+    fn views_of(mismatch: Option<linux::Mismatch>) -> Option<(Vec<View>, Vec<View>)> {
+        mismatch.map(|m| (m.seen, m.missing))
+    }
+
+    #[test]
+    fn processes_every_view_sees_are_not_reported() {
+        let (pid, views) = own_views();
+        assert!(views.mismatch(pid, &HashSet::from([pid]), None, false).is_none());
+        assert!(views.highest().is_some_and(|highest| highest >= pid));
+    }
+
+    #[test]
+    fn processes_missing_from_the_list_are_reported() {
+        let (pid, views) = own_views();
+        assert_eq!(views_of(views.mismatch(pid, &HashSet::new(), None, false)),
+                   Some((vec![View::Readdir, View::Stat, View::Kill], vec![View::List])));
+    }
+
+    #[test]
+    fn processes_hidden_from_readdir_are_reported() {
+        let (pid, mut views) = own_views();
+        views.entries.remove(&pid);
+        views.tasks.remove(&pid);
+        assert_eq!(views_of(views.mismatch(pid, &HashSet::from([pid]), None, false)),
+                   Some((vec![View::Stat, View::Kill, View::List], vec![View::Readdir])));
+        // kill(2) is left out under hidepid
+        assert_eq!(views_of(views.mismatch(pid, &HashSet::from([pid]), None, true)),
+                   Some((vec![View::Stat, View::List], vec![View::Readdir])));
+    }
+
+    #[test]
+    fn threads_are_found_through_their_task_directory() {
+        let (pid, views) = own_views();
+        // SAFETY: gettid has no preconditions and cannot fail
+        let tid = unsafe { libc::gettid() } as u32;
+        assert_eq!(views.tasks.get(&tid), Some(&pid));
+        assert!(views.mismatch(tid, &HashSet::from([tid]), None, false).is_none());
+    }
+
+    #[test]
+    fn processes_started_after_the_list_are_not_reported() {
+        let (pid, views) = own_views();
+        assert!(views.mismatch(pid, &HashSet::new(), Some(0), false).is_none());
+    }
+
+    #[test]
+    fn pids_no_view_sees_are_not_reported() {
+        let (_, views) = own_views();
+        let unused = (1..pid_max()).rev()
+            .find(|pid| !std::path::Path::new(&format!("/proc/{}", pid)).exists())
+            .expect("an unused PID");
+        assert!(views.mismatch(unused, &HashSet::new(), None, false).is_none());
+        // Nor is one the list still has, which exited since it was taken
+        assert!(views.mismatch(unused, &HashSet::from([unused]), None, false).is_none());
+    }
+}
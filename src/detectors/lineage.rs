@@ -0,0 +1,65 @@
+use std::collections::{HashMap, HashSet};
+
+use super::{process_finding, Detector};
+use crate::types::{Finding, MonitorSnapshot, ProcessInfo, Severity};
+
+/// Network-facing services that should never need to start an interactive shell
+///
+/// General-purpose runtimes such as `java` or `node` are left out: build tools,
+/// package scripts and editors built on them start shells all the time.
+const SERVICE_PARENTS: &[&str] = &[
+    "nginx", "apache2", "httpd", "lighttpd", "caddy", "php-fpm", "php-cgi",
+    "tomcat", "gunicorn", "uwsgi", "mysqld", "mariadbd",
+    "postgres", "redis-server", "mongod", "w3wp.exe", "sqlservr.exe",
+];
+
+/// Shells and tools typically spawned by a web shell or remote code execution
+const SHELL_CHILDREN: &[&str] = &[
+    "sh", "bash", "dash", "zsh", "ksh", "csh", "tcsh", "busybox",
+    "nc", "ncat", "netcat", "socat", "telnet", "python", "python3", "perl", "ruby",
+    "cmd.exe", "powershell.exe", "pwsh",
+];
+
+/// Why a parent/child pair is unusual, e.g. a shell spawned by a web server
+pub fn unusual_lineage(parent: &ProcessInfo, child: &ProcessInfo) -> Option<String> {
+    let parent_name = parent.name.to_lowercase();
+    let child_name = child.name.to_lowercase();
+
+    // Prefix match covers versioned or retitled workers such as `php-fpm8.2` or `postgres: writer`
+    let service = SERVICE_PARENTS.iter().any(|s| parent_name.starts_with(s));
+    let shell = SHELL_CHILDREN.contains(&child_name.as_str());
+
+    (service && shell).then(|| format!("{} spawned by service {} (PID {})", child.name, parent.name, parent.pid))
+}
+
+/// Shells and network tools started by server processes
+///
+/// Each child is reported once, in the first snapshot it appears in; the tree
+/// view keeps highlighting it for as long as it runs.
+pub struct SuspiciousLineage;
+
+impl Detector for SuspiciousLineage {
+    fn name(&self) -> &'static str {
+        "suspicious-lineage"
+    }
+
+    fn evaluate(&self, current: &MonitorSnapshot, previous: Option<&MonitorSnapshot>) -> Vec<Finding> {
+        let by_pid: HashMap<u32, &ProcessInfo> = current.processes.iter()
+            .filter(|p| !p.is_thread)
+            .map(|p| (p.pid, p))
+            .collect();
+        // Children already under the same parent last time were reported then
+        let seen: HashSet<_> = previous.map_or_else(HashSet::new, |previous| {
+            previous.processes.iter().map(|p| (p.key(), p.parent_pid)).collect()
+        });
+
+        current.processes.iter()
+            .filter(|p| !p.is_thread && !seen.contains(&(p.key(), p.parent_pid)))
+            .filter_map(|p| {
+                let parent = by_pid.get(&p.parent_pid?)?;
+                let reason = unusual_lineage(parent, p)?;
+                Some(process_finding(self, Severity::High, p, reason))
+            })
+            .collect()
+    }
+}
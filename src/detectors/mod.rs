@@ -0,0 +1,74 @@
+//! Security heuristics evaluated against every collected snapshot.
+//!
+//! Each [`Detector`] looks at the current [`MonitorSnapshot`] (and the previous one,
+//! when there is one) and reports [`Finding`]s. The engine runs every registered
+//! detector and the monitor attaches the combined result to the snapshot.
+//!
+//! [`HiddenProcessScan`] is the exception: it needs the live system rather than a
+//! snapshot, so the monitor runs it itself and adds its findings.
+
+mod builtin;
+mod crossview;
+mod lineage;
+mod pressure;
+
+pub use builtin::{DeletedExecutable, NameMismatch, OrphanedProcess, TempDirExecutable};
+pub use crossview::HiddenProcessScan;
+pub use lineage::{unusual_lineage, SuspiciousLineage};
+pub use pressure::ResourcePressure;
+
+use crate::types::{Finding, MonitorSnapshot, ProcessInfo, Severity};
+
+pub trait Detector {
+    /// Short identifier recorded in every finding this detector produces
+    fn name(&self) -> &'static str;
+
+    /// Inspect `current`, optionally comparing against `previous`
+    fn evaluate(&self, current: &MonitorSnapshot, previous: Option<&MonitorSnapshot>) -> Vec<Finding>;
+}
+
+pub struct DetectorEngine {
+    detectors: Vec<Box<dyn Detector>>,
+}
+
+impl DetectorEngine {
+    /// An engine with no detectors registered
+    pub fn new() -> Self {
+        Self { detectors: Vec::new() }
+    }
+
+    /// An engine with every built-in detector registered
+    pub fn with_builtins() -> Self {
+        let mut engine = Self::new();
+        engine.register(Box::new(TempDirExecutable));
+        engine.register(Box::new(DeletedExecutable));
+        engine.register(Box::new(OrphanedProcess));
+        engine.register(Box::new(NameMismatch));
+        engine.register(Box::new(SuspiciousLineage));
+        engine
+    }
+
+    pub fn register(&mut self, detector: Box<dyn Detector>) {
+        self.detectors.push(detector);
+    }
+
+    /// Run every detector, most severe findings first
+    pub fn evaluate(&self, current: &MonitorSnapshot, previous: Option<&MonitorSnapshot>) -> Vec<Finding> {
+        let mut findings: Vec<Finding> = self.detectors.iter()
+            .flat_map(|detector| detector.evaluate(current, previous))
+            .collect();
+        findings.sort_by_key(|f| std::cmp::Reverse(f.severity));
+        findings
+    }
+}
+
+/// Build a finding attributed to `process`
+pub(crate) fn process_finding(detector: &dyn Detector, severity: Severity, process: &ProcessInfo, message: String) -> Finding {
+    Finding {
+        detector: detector.name().to_string(),
+        severity,
+        process: Some(process.key()),
+        process_name: Some(process.name.clone()),
+        message,
+    }
+}
@@ -0,0 +1,55 @@
+use super::Detector;
+use crate::config::Thresholds;
+use crate::types::{Finding, MonitorSnapshot, PressureStall, Severity};
+
+/// CPU, memory or I/O pressure above the configured thresholds
+///
+/// Medium when some tasks were stalled for longer than the threshold allows,
+/// High when all of them were (`full` pressure).
+pub struct ResourcePressure {
+    thresholds: Thresholds,
+}
+
+impl ResourcePressure {
+    pub fn new(thresholds: Thresholds) -> Self {
+        Self { thresholds }
+    }
+}
+
+impl Detector for ResourcePressure {
+    fn name(&self) -> &'static str {
+        "pressure"
+    }
+
+    fn evaluate(&self, current: &MonitorSnapshot, _previous: Option<&MonitorSnapshot>) -> Vec<Finding> {
+        let Some(pressure) = &current.system_info.pressure else {
+            return Vec::new();
+        };
+
+        let resources: [(&str, Option<PressureStall>, Option<f64>); 3] = [
+            ("cpu", pressure.cpu, self.thresholds.cpu_pressure),
+            ("memory", pressure.memory, self.thresholds.memory_pressure),
+            ("io", pressure.io, self.thresholds.io_pressure),
+        ];
+        resources.into_iter()
+            .filter_map(|(resource, stall, threshold)| {
+                let (stall, threshold) = (stall?, threshold?);
+                if stall.some.avg10 <= threshold {
+                    return None;
+                }
+                let full = stall.full.is_some_and(|full| full.avg10 > threshold);
+                Some(Finding {
+                    detector: self.name().to_string(),
+                    severity: if full { Severity::High } else { Severity::Medium },
+                    process: None,
+                    process_name: None,
+                    message: format!("{} pressure: {} tasks stalled {:.1}% of the last 10s (threshold {}%)",
+                                     resource,
+                                     if full { "all" } else { "some" },
+                                     if full { stall.full.map_or(0.0, |f| f.avg10) } else { stall.some.avg10 },
+                                     threshold),
+                })
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,246 @@
+//! Comparison of two saved snapshots.
+
+use std::collections::{BTreeMap, HashMap};
+use std::io;
+use std::path::Path;
+
+use chrono::{DateTime, Local};
+use serde::Serialize;
+
+use crate::capture::CaptureIndex;
+use crate::network;
+use crate::types::{MonitorSnapshot, NetworkConnection, ProcessInfo, ProcessKey};
+use crate::utils::truncate_string;
+
+/// Command lines are cut to this many characters in the human-readable output
+const CMD_WIDTH: usize = 100;
+
+#[derive(Debug, Serialize)]
+pub struct SnapshotDiff {
+    pub from: DateTime<Local>,
+    pub to: DateTime<Local>,
+    pub started: Vec<ProcessInfo>,
+    pub exited: Vec<ProcessInfo>,
+    pub reparented: Vec<ProcessChange<Option<u32>>>,
+    pub exe_changed: Vec<ProcessChange<Option<String>>>,
+    pub user_changed: Vec<ProcessChange<UserIds>>,
+    pub cmd_changed: Vec<ProcessChange<Vec<String>>>,
+    pub connections_opened: Vec<NetworkConnection>,
+    pub connections_closed: Vec<NetworkConnection>,
+}
+
+/// One attribute of a process that differs between the two snapshots
+#[derive(Debug, Serialize)]
+pub struct ProcessChange<T> {
+    pub key: ProcessKey,
+    pub name: String,
+    pub before: T,
+    pub after: T,
+}
+
+/// Real, effective and saved UIDs; a setuid escalation changes only the last two
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct UserIds {
+    pub real: Option<u32>,
+    pub effective: Option<u32>,
+    pub saved: Option<u32>,
+}
+
+impl UserIds {
+    fn of(process: &ProcessInfo) -> Self {
+        Self {
+            real: process.user_id,
+            effective: process.effective_user_id,
+            saved: process.saved_user_id,
+        }
+    }
+}
+
+impl SnapshotDiff {
+    pub fn between(from: &MonitorSnapshot, to: &MonitorSnapshot) -> Self {
+        // Threads start and exit all the time; they are compared as part of their process
+        let before: BTreeMap<ProcessKey, &ProcessInfo> = from.processes.iter()
+            .filter(|p| !p.is_thread)
+            .map(|p| (p.key(), p))
+            .collect();
+        let after: BTreeMap<ProcessKey, &ProcessInfo> = to.processes.iter()
+            .filter(|p| !p.is_thread)
+            .map(|p| (p.key(), p))
+            .collect();
+
+        let started = after.iter()
+            .filter(|(key, _)| !before.contains_key(key))
+            .map(|(_, p)| (*p).clone())
+            .collect();
+        let exited = before.iter()
+            .filter(|(key, _)| !after.contains_key(key))
+            .map(|(_, p)| (*p).clone())
+            .collect();
+
+        let mut diff = Self {
+            from: from.timestamp,
+            to: to.timestamp,
+            started,
+            exited,
+            reparented: Vec::new(),
+            exe_changed: Vec::new(),
+            user_changed: Vec::new(),
+            cmd_changed: Vec::new(),
+            connections_opened: Vec::new(),
+            connections_closed: Vec::new(),
+        };
+
+        for (key, old) in &before {
+            let Some(new) = after.get(key) else {
+                continue;
+            };
+            if old.parent_pid != new.parent_pid {
+                diff.reparented.push(change(new, old.parent_pid, new.parent_pid));
+            }
+            if old.exe_path != new.exe_path {
+                diff.exe_changed.push(change(new, old.exe_path.clone(), new.exe_path.clone()));
+            }
+            if UserIds::of(old) != UserIds::of(new) {
+                diff.user_changed.push(change(new, UserIds::of(old), UserIds::of(new)));
+            }
+            if old.cmd != new.cmd {
+                diff.cmd_changed.push(change(new, old.cmd.clone(), new.cmd.clone()));
+            }
+        }
+
+        let old_connections: HashMap<_, &NetworkConnection> = from.network_connections.iter()
+            .map(|c| (connection_key(c), c))
+            .collect();
+        let new_connections: HashMap<_, &NetworkConnection> = to.network_connections.iter()
+            .map(|c| (connection_key(c), c))
+            .collect();
+
+        diff.connections_opened = to.network_connections.iter()
+            .filter(|c| !old_connections.contains_key(&connection_key(c)))
+            .cloned()
+            .collect();
+        diff.connections_closed = from.network_connections.iter()
+            .filter(|c| !new_connections.contains_key(&connection_key(c)))
+            .cloned()
+            .collect();
+
+        diff
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.started.is_empty()
+            && self.exited.is_empty()
+            && self.reparented.is_empty()
+            && self.exe_changed.is_empty()
+            && self.user_changed.is_empty()
+            && self.cmd_changed.is_empty()
+            && self.connections_opened.is_empty()
+            && self.connections_closed.is_empty()
+    }
+
+    pub fn print(&self) {
+        println!("🔀 Snapshot diff [{}] -> [{}]",
+                 self.from.format("%Y-%m-%d %H:%M:%S"),
+                 self.to.format("%Y-%m-%d %H:%M:%S"));
+        println!("{}", "─".repeat(80));
+
+        if self.is_empty() {
+            println!("No differences");
+            return;
+        }
+
+        print_section("🚀 Started", &self.started, format_process);
+        print_section("💀 Exited", &self.exited, format_process);
+        print_section("👪 Re-parented", &self.reparented, |c| {
+            format!("{} (PID: {}) PPID {} -> {}", c.name, c.key.pid, optional(&c.before), optional(&c.after))
+        });
+        print_section("📦 Executable changed", &self.exe_changed, |c| {
+            format!("{} (PID: {}) {} -> {}", c.name, c.key.pid, optional(&c.before), optional(&c.after))
+        });
+        print_section("👤 User changed", &self.user_changed, |c| {
+            let changed: Vec<String> = [
+                ("UID", c.before.real, c.after.real),
+                ("EUID", c.before.effective, c.after.effective),
+                ("SUID", c.before.saved, c.after.saved),
+            ]
+                .into_iter()
+                .filter(|(_, before, after)| before != after)
+                .map(|(label, before, after)| format!("{} {} -> {}", label, optional(&before), optional(&after)))
+                .collect();
+            format!("{} (PID: {}) {}", c.name, c.key.pid, changed.join(", "))
+        });
+        print_section("📝 Command line changed", &self.cmd_changed, |c| {
+            format!("{} (PID: {})\n      - {}\n      + {}",
+                    c.name,
+                    c.key.pid,
+                    truncate_string(&c.before.join(" "), CMD_WIDTH),
+                    truncate_string(&c.after.join(" "), CMD_WIDTH))
+        });
+        print_section("🔌 Connections opened", &self.connections_opened, format_connection);
+        print_section("🔒 Connections closed", &self.connections_closed, format_connection);
+    }
+}
+
+/// Diff the last snapshot stored in each file
+pub fn diff_files(from: &Path, to: &Path) -> io::Result<SnapshotDiff> {
+    let from = last_snapshot(from)?;
+    let to = last_snapshot(to)?;
+    Ok(SnapshotDiff::between(&from, &to))
+}
+
+fn last_snapshot(path: &Path) -> io::Result<MonitorSnapshot> {
+    let mut capture = CaptureIndex::open(path)?;
+    let last = capture.len().checked_sub(1).ok_or_else(|| {
+        io::Error::new(io::ErrorKind::InvalidData, format!("{} contains no snapshots", path.display()))
+    })?;
+    capture.get(last)
+}
+
+fn change<T>(process: &ProcessInfo, before: T, after: T) -> ProcessChange<T> {
+    ProcessChange {
+        key: process.key(),
+        name: process.name.clone(),
+        before,
+        after,
+    }
+}
+
+/// Sockets are matched on their endpoints and inode, since a PID can own many
+fn connection_key(conn: &NetworkConnection) -> (&str, &str, u16, &str, u16, u64) {
+    (&conn.protocol, &conn.local_addr, conn.local_port, &conn.remote_addr, conn.remote_port, conn.inode)
+}
+
+fn format_process(process: &ProcessInfo) -> String {
+    format!("{} (PID: {}, PPID: {}) {}",
+            process.name,
+            process.pid,
+            optional(&process.parent_pid),
+            truncate_string(&process.cmd.join(" "), CMD_WIDTH))
+}
+
+fn format_connection(conn: &NetworkConnection) -> String {
+    let (local, remote) = network::connection_endpoints(conn);
+    format!("{} {} -> {} [{}] {} (PID: {})",
+            conn.protocol,
+            local,
+            remote,
+            conn.state,
+            conn.process_name,
+            conn.pid)
+}
+
+fn optional<T: ToString>(value: &Option<T>) -> String {
+    value.as_ref().map_or("-".to_string(), |v| v.to_string())
+}
+
+fn print_section<T>(title: &str, items: &[T], format: impl Fn(&T) -> String) {
+    if items.is_empty() {
+        return;
+    }
+
+    println!("{} ({})", title, items.len());
+    for item in items {
+        println!("  {}", format(item));
+    }
+    println!();
+}
@@ -0,0 +1,745 @@
+//! Process filter expressions, e.g. `user=www-data and cpu>20 and exe~"/tmp/"`.
+//!
+//! An expression is a list of conditions joined with `and`/`or` (or `&&`/`||`),
+//! negated with `not`/`!` and grouped with parentheses. A condition is either
+//! `field op value` or a bare word, which matches process names containing it
+//! (case-insensitive) like the original `--filter`.
+//!
+//! | Operators              | Fields                                                     |
+//! |------------------------|------------------------------------------------------------|
+//! | `= != > >= < <=`       | `pid ppid uid euid gid cpu mem threads uptime`             |
+//! | `= != ~ !~`            | `name cmd exe user group status`                           |
+//! | `= !=`                 | `deleted thread` (`true`/`false`)                          |
+//!
+//! `=` on text is a case-insensitive exact match and `~` a regular expression
+//! search. `mem` is in KB and takes `K`/`M`/`G` suffixes, `uptime` is in
+//! seconds and takes `s`/`m`/`h`/`d`. `user` and `group` match the name or the
+//! numeric ID.
+
+use std::collections::HashSet;
+use std::fmt;
+use std::str::FromStr;
+
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+use crate::types::{FilteredCount, MonitorSnapshot, ProcessInfo, ProcessKey};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct Filter {
+    source: String,
+    expr: Expr,
+}
+
+impl Filter {
+    pub fn parse(source: &str) -> Result<Self, FilterError> {
+        let tokens = tokenize(source)?;
+        let mut parser = Parser { tokens, position: 0, end: source.len() };
+        let expr = parser.parse_or()?;
+        if let Some(token) = parser.tokens.get(parser.position) {
+            return Err(FilterError::new("expected 'and' or 'or'", token.offset));
+        }
+        Ok(Self { source: source.trim().to_string(), expr })
+    }
+
+    /// `now` is the snapshot time in epoch seconds, used for `uptime`
+    pub fn matches(&self, process: &ProcessInfo, now: u64) -> bool {
+        self.expr.matches(process, now)
+    }
+
+    /// Narrow a recorded snapshot as if it had been collected with this filter
+    pub fn apply(&self, snapshot: &mut MonitorSnapshot) {
+        let now = snapshot.timestamp.timestamp().max(0) as u64;
+        let listed: HashSet<ProcessKey> = snapshot.processes.iter().map(|p| p.key()).collect();
+        let before = snapshot.processes.iter().filter(|p| !p.is_thread).count();
+        snapshot.processes.retain(|p| self.matches(p, now));
+        let excluded = before - snapshot.processes.iter().filter(|p| !p.is_thread).count();
+        if excluded > 0 {
+            snapshot.filtered.push(FilteredCount { rule: format!("filter: {}", self), count: excluded });
+        }
+        snapshot.events.retain(|e| self.matches(&e.process, now));
+
+        let visible: HashSet<ProcessKey> = snapshot.processes.iter().map(|p| p.key()).collect();
+        let pids: HashSet<u32> = visible.iter().map(|k| k.pid).collect();
+        // Findings about the whole system or about processes the list never had, such
+        // as hidden ones, are not about anything the filter excluded
+        snapshot.findings.retain(|f| f.process.is_none_or(|k| visible.contains(&k) || !listed.contains(&k)));
+        snapshot.network_connections.retain(|c| pids.contains(&c.pid));
+    }
+}
+
+impl FromStr for Filter {
+    type Err = FilterError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Filter::parse(s)
+    }
+}
+
+impl TryFrom<String> for Filter {
+    type Error = FilterError;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        Filter::parse(&s)
+    }
+}
+
+impl From<Filter> for String {
+    fn from(filter: Filter) -> Self {
+        filter.source
+    }
+}
+
+impl fmt::Display for Filter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.source)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct FilterError {
+    pub message: String,
+    // Byte offset into the expression
+    pub offset: usize,
+}
+
+impl FilterError {
+    fn new(message: impl Into<String>, offset: usize) -> Self {
+        Self { message: message.into(), offset }
+    }
+}
+
+impl fmt::Display for FilterError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} at column {}", self.message, self.offset + 1)
+    }
+}
+
+impl std::error::Error for FilterError {}
+
+#[derive(Debug, Clone)]
+enum Expr {
+    And(Box<Expr>, Box<Expr>),
+    Or(Box<Expr>, Box<Expr>),
+    Not(Box<Expr>),
+    NameContains(String),
+    Number(NumberField, Comparison, f64),
+    Text(TextField, TextPattern, bool),
+    Flag(FlagField, bool),
+}
+
+impl Expr {
+    fn matches(&self, process: &ProcessInfo, now: u64) -> bool {
+        match self {
+            Expr::And(a, b) => a.matches(process, now) && b.matches(process, now),
+            Expr::Or(a, b) => a.matches(process, now) || b.matches(process, now),
+            Expr::Not(e) => !e.matches(process, now),
+            Expr::NameContains(needle) => process.name.to_lowercase().contains(needle),
+            Expr::Number(field, comparison, value) => match field.value(process, now) {
+                Some(actual) => comparison.holds(actual, *value),
+                // Unknown values (no parent, unreadable UID) only satisfy `!=`
+                None => *comparison == Comparison::Ne,
+            },
+            Expr::Text(field, pattern, negate) => {
+                let found = field.values(process).iter().any(|v| pattern.matches(v));
+                found != *negate
+            }
+            Expr::Flag(field, value) => field.value(process) == *value,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Comparison {
+    Eq,
+    Ne,
+    Gt,
+    Ge,
+    Lt,
+    Le,
+}
+
+impl Comparison {
+    fn holds(self, actual: f64, expected: f64) -> bool {
+        match self {
+            Comparison::Eq => actual == expected,
+            Comparison::Ne => actual != expected,
+            Comparison::Gt => actual > expected,
+            Comparison::Ge => actual >= expected,
+            Comparison::Lt => actual < expected,
+            Comparison::Le => actual <= expected,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+enum NumberField {
+    Pid,
+    Ppid,
+    Uid,
+    Euid,
+    Gid,
+    Cpu,
+    Mem,
+    Threads,
+    Uptime,
+}
+
+impl NumberField {
+    fn value(self, process: &ProcessInfo, now: u64) -> Option<f64> {
+        match self {
+            NumberField::Pid => Some(process.pid as f64),
+            NumberField::Ppid => process.parent_pid.map(|p| p as f64),
+            NumberField::Uid => process.user_id.map(|u| u as f64),
+            NumberField::Euid => process.effective_user_id.map(|u| u as f64),
+            NumberField::Gid => process.group_id.map(|g| g as f64),
+            NumberField::Cpu => Some(process.cpu_usage as f64),
+            NumberField::Mem => Some((process.memory / 1024) as f64),
+            NumberField::Threads => (process.threads > 0).then_some(process.threads as f64),
+            NumberField::Uptime => Some(now.saturating_sub(process.start_time) as f64),
+        }
+    }
+
+    /// Multipliers for unit suffixes on values of this field
+    fn units(self) -> &'static [(&'static str, f64)] {
+        match self {
+            NumberField::Mem => &[("k", 1.0), ("m", 1024.0), ("g", 1_048_576.0)],
+            NumberField::Uptime => &[("s", 1.0), ("m", 60.0), ("h", 3_600.0), ("d", 86_400.0)],
+            _ => &[],
+        }
+    }
+
+    fn parse_value(self, text: &str) -> Option<f64> {
+        let lower = text.to_lowercase();
+        for (suffix, multiplier) in self.units() {
+            if let Some(number) = lower.strip_suffix(suffix) {
+                return number.parse::<f64>().ok().map(|n| n * multiplier);
+            }
+        }
+        lower.parse().ok()
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+enum TextField {
+    Name,
+    Cmd,
+    Exe,
+    User,
+    Group,
+    Status,
+}
+
+impl TextField {
+    /// Candidate strings; the condition holds if any of them matches
+    fn values(self, process: &ProcessInfo) -> Vec<String> {
+        let with_id = |name: &Option<String>, id: Option<u32>| {
+            name.iter().cloned().chain(id.map(|id| id.to_string())).collect()
+        };
+        match self {
+            TextField::Name => vec![process.name.clone()],
+            TextField::Cmd => vec![process.cmd.join(" ")],
+            TextField::Exe => process.exe_path.iter().cloned().collect(),
+            TextField::User => with_id(&process.user_name, process.user_id),
+            TextField::Group => with_id(&process.group_name, process.group_id),
+            TextField::Status => vec![process.status.clone()],
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+enum TextPattern {
+    // Lowercased for a case-insensitive comparison
+    Exact(String),
+    Regex(Regex),
+}
+
+impl TextPattern {
+    fn matches(&self, value: &str) -> bool {
+        match self {
+            TextPattern::Exact(expected) => value.to_lowercase() == *expected,
+            TextPattern::Regex(regex) => regex.is_match(value),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+enum FlagField {
+    Deleted,
+    Thread,
+}
+
+impl FlagField {
+    fn value(self, process: &ProcessInfo) -> bool {
+        match self {
+            FlagField::Deleted => process.exe_deleted,
+            FlagField::Thread => process.is_thread,
+        }
+    }
+}
+
+enum Field {
+    Number(NumberField),
+    Text(TextField),
+    Flag(FlagField),
+}
+
+impl Field {
+    fn parse(name: &str) -> Option<Field> {
+        Some(match name.to_lowercase().as_str() {
+            "pid" => Field::Number(NumberField::Pid),
+            "ppid" => Field::Number(NumberField::Ppid),
+            "uid" => Field::Number(NumberField::Uid),
+            "euid" => Field::Number(NumberField::Euid),
+            "gid" => Field::Number(NumberField::Gid),
+            "cpu" => Field::Number(NumberField::Cpu),
+            "mem" | "memory" => Field::Number(NumberField::Mem),
+            "threads" => Field::Number(NumberField::Threads),
+            "uptime" => Field::Number(NumberField::Uptime),
+            "name" => Field::Text(TextField::Name),
+            "cmd" => Field::Text(TextField::Cmd),
+            "exe" => Field::Text(TextField::Exe),
+            "user" => Field::Text(TextField::User),
+            "group" => Field::Text(TextField::Group),
+            "status" => Field::Text(TextField::Status),
+            "deleted" => Field::Flag(FlagField::Deleted),
+            "thread" => Field::Flag(FlagField::Thread),
+            _ => return None,
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Operator {
+    Eq,
+    Ne,
+    Gt,
+    Ge,
+    Lt,
+    Le,
+    Match,
+    NotMatch,
+}
+
+#[derive(Debug, PartialEq)]
+enum TokenKind {
+    LParen,
+    RParen,
+    And,
+    Or,
+    Not,
+    Op(Operator),
+    Word(String),
+}
+
+struct Token {
+    kind: TokenKind,
+    offset: usize,
+}
+
+fn tokenize(source: &str) -> Result<Vec<Token>, FilterError> {
+    let mut tokens = Vec::new();
+    let mut chars = source.char_indices().peekable();
+
+    while let Some(&(offset, c)) = chars.peek() {
+        if c.is_whitespace() {
+            chars.next();
+            continue;
+        }
+        chars.next();
+        let next = chars.peek().map(|&(_, c)| c);
+
+        let kind = match (c, next) {
+            ('(', _) => TokenKind::LParen,
+            (')', _) => TokenKind::RParen,
+            ('&', Some('&')) => {
+                chars.next();
+                TokenKind::And
+            }
+            ('|', Some('|')) => {
+                chars.next();
+                TokenKind::Or
+            }
+            ('!' | '>' | '<' | '=', Some('=')) => {
+                chars.next();
+                TokenKind::Op(match c {
+                    '!' => Operator::Ne,
+                    '>' => Operator::Ge,
+                    '<' => Operator::Le,
+                    _ => Operator::Eq,
+                })
+            }
+            ('!', Some('~')) => {
+                chars.next();
+                TokenKind::Op(Operator::NotMatch)
+            }
+            ('!', _) => TokenKind::Not,
+            ('=', _) => TokenKind::Op(Operator::Eq),
+            ('~', _) => TokenKind::Op(Operator::Match),
+            ('>', _) => TokenKind::Op(Operator::Gt),
+            ('<', _) => TokenKind::Op(Operator::Lt),
+            ('"' | '\'', _) => {
+                let mut value = String::new();
+                let mut closed = false;
+                while let Some((_, ch)) = chars.next() {
+                    match ch {
+                        '\\' => {
+                            // Keep the backslash unless it escapes a quote, so regex escapes survive
+                            match chars.next() {
+                                Some((_, q)) if q == c => value.push(q),
+                                Some((_, other)) => {
+                                    value.push('\\');
+                                    value.push(other);
+                                }
+                                None => value.push('\\'),
+                            }
+                        }
+                        ch if ch == c => {
+                            closed = true;
+                            break;
+                        }
+                        ch => value.push(ch),
+                    }
+                }
+                if !closed {
+                    return Err(FilterError::new("unterminated string", offset));
+                }
+                TokenKind::Word(value)
+            }
+            _ => {
+                let mut word = c.to_string();
+                while let Some(&(_, ch)) = chars.peek() {
+                    if ch.is_whitespace() || "()=!~<>&|\"'".contains(ch) {
+                        break;
+                    }
+                    word.push(ch);
+                    chars.next();
+                }
+                match word.to_lowercase().as_str() {
+                    "and" => TokenKind::And,
+                    "or" => TokenKind::Or,
+                    "not" => TokenKind::Not,
+                    _ => TokenKind::Word(word),
+                }
+            }
+        };
+        tokens.push(Token { kind, offset });
+    }
+
+    Ok(tokens)
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    position: usize,
+    // Offset reported for errors at the end of the input
+    end: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&TokenKind> {
+        self.tokens.get(self.position).map(|t| &t.kind)
+    }
+
+    fn offset(&self) -> usize {
+        self.tokens.get(self.position).map_or(self.end, |t| t.offset)
+    }
+
+    fn parse_or(&mut self) -> Result<Expr, FilterError> {
+        let mut left = self.parse_and()?;
+        while self.peek() == Some(&TokenKind::Or) {
+            self.position += 1;
+            left = Expr::Or(Box::new(left), Box::new(self.parse_and()?));
+        }
+        Ok(left)
+    }
+
+    fn parse_and(&mut self) -> Result<Expr, FilterError> {
+        let mut left = self.parse_not()?;
+        while self.peek() == Some(&TokenKind::And) {
+            self.position += 1;
+            left = Expr::And(Box::new(left), Box::new(self.parse_not()?));
+        }
+        Ok(left)
+    }
+
+    fn parse_not(&mut self) -> Result<Expr, FilterError> {
+        if self.peek() == Some(&TokenKind::Not) {
+            self.position += 1;
+            return Ok(Expr::Not(Box::new(self.parse_not()?)));
+        }
+        self.parse_primary()
+    }
+
+    fn parse_primary(&mut self) -> Result<Expr, FilterError> {
+        let offset = self.offset();
+        match self.tokens.get(self.position).map(|t| &t.kind) {
+            Some(TokenKind::LParen) => {
+                self.position += 1;
+                let expr = self.parse_or()?;
+                if self.peek() != Some(&TokenKind::RParen) {
+                    return Err(FilterError::new("expected ')'", self.offset()));
+                }
+                self.position += 1;
+                Ok(expr)
+            }
+            Some(TokenKind::Word(word)) => {
+                let word = word.clone();
+                self.position += 1;
+                match self.peek() {
+                    Some(&TokenKind::Op(op)) => {
+                        self.position += 1;
+                        self.parse_condition(&word, offset, op)
+                    }
+                    _ => Ok(Expr::NameContains(word.to_lowercase())),
+                }
+            }
+            _ => Err(FilterError::new("expected a condition", offset)),
+        }
+    }
+
+    fn parse_condition(&mut self, name: &str, name_offset: usize, op: Operator) -> Result<Expr, FilterError> {
+        let field = Field::parse(name)
+            .ok_or_else(|| FilterError::new(format!("unknown field '{}'", name), name_offset))?;
+
+        let offset = self.offset();
+        let Some(TokenKind::Word(value)) = self.peek() else {
+            return Err(FilterError::new("expected a value", offset));
+        };
+        let value = value.clone();
+        self.position += 1;
+
+        let unsupported = || FilterError::new(format!("operator not supported for field '{}'", name), name_offset);
+
+        match field {
+            Field::Number(field) => {
+                let comparison = match op {
+                    Operator::Eq => Comparison::Eq,
+                    Operator::Ne => Comparison::Ne,
+                    Operator::Gt => Comparison::Gt,
+                    Operator::Ge => Comparison::Ge,
+                    Operator::Lt => Comparison::Lt,
+                    Operator::Le => Comparison::Le,
+                    Operator::Match | Operator::NotMatch => return Err(unsupported()),
+                };
+                let number = field.parse_value(&value)
+                    .ok_or_else(|| FilterError::new(format!("invalid number '{}'", value), offset))?;
+                Ok(Expr::Number(field, comparison, number))
+            }
+            Field::Text(field) => {
+                let (pattern, negate) = match op {
+                    Operator::Eq | Operator::Ne => (TextPattern::Exact(value.to_lowercase()), op == Operator::Ne),
+                    Operator::Match | Operator::NotMatch => {
+                        let regex = Regex::new(&value)
+                            // The last line of the regex error names the problem; the rest is a diagram
+                            .map_err(|e| {
+                                let reason = e.to_string().lines().last().unwrap_or_default().trim_start_matches("error: ").to_string();
+                                FilterError::new(format!("invalid regex: {}", reason), offset)
+                            })?;
+                        (TextPattern::Regex(regex), op == Operator::NotMatch)
+                    }
+                    _ => return Err(unsupported()),
+                };
+                Ok(Expr::Text(field, pattern, negate))
+            }
+            Field::Flag(field) => {
+                let flag = match value.to_lowercase().as_str() {
+                    "true" | "yes" | "1" => true,
+                    "false" | "no" | "0" => false,
+                    _ => return Err(FilterError::new(format!("expected true or false, got '{}'", value), offset)),
+                };
+                match op {
+                    Operator::Eq => Ok(Expr::Flag(field, flag)),
+                    Operator::Ne => Ok(Expr::Flag(field, !flag)),
+                    _ => Err(unsupported()),
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::fixtures::{self, START};
+    use crate::config::Thresholds;
+    use crate::detectors::{Detector, ResourcePressure};
+    use crate::types::{Finding, Pressure, PressureAverages, PressureStall, Severity};
+
+    /// One hour after the test process started
+    const NOW: u64 = START as u64 + 3600;
+
+    fn nginx() -> ProcessInfo {
+        fixtures::process(420, json!({
+            "name": "nginx",
+            "cmd": ["/usr/sbin/nginx", "-g", "daemon off;", "it's", "say \"hi\""],
+            "cpu_usage": 50.0,
+            "memory": 2 * 1024 * 1024,
+            "user_id": 33,
+            "user_name": "www-data",
+            "exe_path": "/usr/sbin/nginx",
+        }))
+    }
+
+    fn matches(source: &str) -> bool {
+        Filter::parse(source).unwrap_or_else(|e| panic!("{}: {}", source, e)).matches(&nginx(), NOW)
+    }
+
+    fn error(source: &str) -> FilterError {
+        Filter::parse(source).err().unwrap_or_else(|| panic!("{} parsed", source))
+    }
+
+    #[test]
+    fn and_binds_tighter_than_or() {
+        assert!(matches("name=nginx or name=foo and cpu>90"));
+        assert!(!matches("(name=nginx or name=foo) and cpu>90"));
+        assert!(matches("name=foo or cpu>90 or pid=420"));
+        assert!(matches("name=nginx && cpu>40 || name=foo"));
+    }
+
+    #[test]
+    fn not_negates_the_next_condition() {
+        assert!(matches("not name=foo"));
+        assert!(matches("!name=foo"));
+        assert!(matches("not not name=nginx"));
+        assert!(!matches("not name=foo and name=foo"));
+        assert!(matches("not (name=foo and cpu>40)"));
+    }
+
+    #[test]
+    fn quoted_values_and_escapes() {
+        assert!(matches(r#"cmd~"daemon off;""#));
+        assert!(matches(r"cmd~'it\'s'"));
+        assert!(matches(r#"cmd~"say \"hi\"""#));
+        // Other escapes reach the regex untouched
+        assert!(matches(r#"exe~"sbin.nginx""#));
+        assert!(!matches(r#"exe~"sbin\.nginx""#));
+        assert!(matches(r#"exe~"^/usr/sbin/nginx$""#));
+    }
+
+    #[test]
+    fn text_equality_ignores_case() {
+        assert!(matches("name=NGINX"));
+        assert!(matches("user=WWW-DATA"));
+        assert!(matches("user=33"));
+        assert!(!matches("name=ngin"));
+        assert!(matches("name!=apache2"));
+    }
+
+    #[test]
+    fn regex_search() {
+        assert!(matches("name~^ng"));
+        assert!(!matches("name!~^ng"));
+        assert!(matches("exe~/sbin/"));
+        assert!(!matches(r#"exe~"^/tmp/""#));
+    }
+
+    #[test]
+    fn numeric_comparisons_and_units() {
+        assert!(matches("cpu>40"));
+        assert!(!matches("cpu<40"));
+        assert!(matches("cpu>=50 and cpu<=50"));
+        assert!(matches("mem>=2M and mem<3m"));
+        assert!(matches("mem=2048"));
+        assert!(matches("uptime>59m and uptime<=1h"));
+        assert!(matches("uid=33 and euid!=0"));
+        // Unknown values only satisfy `!=`
+        assert!(!matches("euid=0") && !matches("euid>0"));
+    }
+
+    #[test]
+    fn flags() {
+        assert!(matches("deleted=false"));
+        assert!(matches("thread!=yes"));
+        assert!(!matches("deleted=1"));
+    }
+
+    #[test]
+    fn bare_words_match_names() {
+        assert!(matches("ngi"));
+        assert!(matches("NGINX"));
+        assert!(!matches("apache"));
+        assert!(matches("apache or ngin"));
+    }
+
+    #[test]
+    fn parse_errors_point_at_the_problem() {
+        let cases = [
+            ("foo=1", "unknown field 'foo'", 0),
+            ("cpu>abc", "invalid number 'abc'", 4),
+            ("name>3", "operator not supported for field 'name'", 0),
+            ("cpu~3", "operator not supported for field 'cpu'", 0),
+            ("(name=a", "expected ')'", 7),
+            ("name=", "expected a value", 5),
+            ("name=a name=b", "expected 'and' or 'or'", 7),
+            ("name=a and", "expected a condition", 10),
+            ("cmd~\"abc", "unterminated string", 4),
+            ("deleted=maybe", "expected true or false, got 'maybe'", 8),
+        ];
+        for (source, message, offset) in cases {
+            let e = error(source);
+            assert_eq!((e.message.as_str(), e.offset), (message, offset), "{}", source);
+        }
+        assert!(error("exe~\"(\"").message.starts_with("invalid regex"));
+    }
+
+    #[test]
+    fn display_round_trips() {
+        for source in ["  user=www-data and (cpu>20 or exe~\"/tmp/\")  ", r"cmd~'it\'s'", "not nginx", "mem>1G"] {
+            let filter = Filter::parse(source).unwrap();
+            assert_eq!(filter.to_string(), source.trim());
+            let reparsed = Filter::parse(&filter.to_string()).unwrap();
+            assert_eq!(reparsed.to_string(), filter.to_string());
+            assert_eq!(reparsed.matches(&nginx(), NOW), filter.matches(&nginx(), NOW));
+        }
+    }
+
+    #[test]
+    fn apply_keeps_findings_about_nothing_it_excluded() {
+        let finding = |detector: &str, process: Option<ProcessKey>| Finding {
+            detector: detector.to_string(),
+            severity: Severity::High,
+            process,
+            process_name: None,
+            message: String::new(),
+        };
+        let other = fixtures::process(500, json!({}));
+        let mut snapshot = fixtures::snapshot(3600, vec![nginx(), other.clone()]);
+        snapshot.findings = vec![
+            finding("temp-dir-exe", Some(nginx().key())),
+            finding("deleted-exe", Some(other.key())),
+            // A process missing from the list, found by the cross-view scan
+            finding("hidden-process", Some(ProcessKey { pid: 31337, start: 42 })),
+            finding("system-wide", None),
+        ];
+
+        Filter::parse("name=nginx").unwrap().apply(&mut snapshot);
+        let detectors: Vec<&str> = snapshot.findings.iter().map(|f| f.detector.as_str()).collect();
+        assert_eq!(detectors, ["temp-dir-exe", "hidden-process", "system-wide"]);
+        assert_eq!(snapshot.processes.len(), 1);
+        assert_eq!(snapshot.filtered[0].count, 1);
+    }
+
+    #[test]
+    fn apply_keeps_pressure_findings() {
+        let mut snapshot = fixtures::snapshot(3600, vec![nginx(), fixtures::process(500, json!({}))]);
+        snapshot.system_info.pressure = Some(Pressure {
+            cpu: None,
+            memory: Some(PressureStall {
+                some: PressureAverages { avg10: 40.0, avg60: 20.0, avg300: 5.0, total: 1_000_000 },
+                full: None,
+            }),
+            io: None,
+        });
+        let thresholds = Thresholds { memory_pressure: Some(10.0), ..Thresholds::default() };
+        snapshot.findings = ResourcePressure::new(thresholds).evaluate(&snapshot, None);
+        assert_eq!(snapshot.findings.len(), 1);
+
+        Filter::parse("name=nginx").unwrap().apply(&mut snapshot);
+        assert_eq!(snapshot.findings.len(), 1);
+        assert!(snapshot.findings[0].message.starts_with("memory pressure: some tasks stalled 40.0%"));
+    }
+}
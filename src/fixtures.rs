@@ -0,0 +1,44 @@
+//! Processes and snapshots for unit tests, built from JSON the way captures are read.
+
+use chrono::DateTime;
+use serde_json::{json, Value};
+
+use crate::types::{MonitorSnapshot, ProcessInfo};
+
+/// Epoch seconds at which fixture processes start and the first fixture snapshot is taken
+pub const START: i64 = 1_700_000_000;
+
+/// A sleeping process `/usr/bin/proc<pid>` of UID 1000, with `fields` overriding any of its fields
+pub fn process(pid: u32, fields: Value) -> ProcessInfo {
+    let mut process = json!({
+        "pid": pid,
+        "name": format!("proc{}", pid),
+        "cmd": [format!("/usr/bin/proc{}", pid)],
+        "cpu_usage": 0.0,
+        "memory": 4096,
+        "parent_pid": 1,
+        "start_time": START,
+        "user_id": 1000,
+        "status": "Sleep",
+        "exe_path": format!("/usr/bin/proc{}", pid),
+    });
+    if let (Some(process), Value::Object(fields)) = (process.as_object_mut(), fields) {
+        process.extend(fields);
+    }
+    serde_json::from_value(process).expect("fixture process")
+}
+
+/// A snapshot of `processes` taken `second` seconds after [`START`]
+pub fn snapshot(second: i64, processes: Vec<ProcessInfo>) -> MonitorSnapshot {
+    serde_json::from_value(json!({
+        "timestamp": DateTime::from_timestamp(START + second, 0).expect("fixture timestamp"),
+        "processes": processes,
+        "network_connections": [],
+        "system_info": {
+            "total_memory": 8192,
+            "used_memory": 4096,
+            "cpu_count": 4,
+            "uptime": 100 + second,
+        },
+    })).expect("fixture snapshot")
+}
@@ -0,0 +1,101 @@
+//! Rolling CPU and memory history of each process.
+//!
+//! Snapshots only carry the current figures, so the interface records a few
+//! samples per [`ProcessKey`] as snapshots arrive and draws them as sparklines.
+//! Samples of processes that are no longer listed are dropped with them, so a
+//! reused PID starts with an empty history.
+
+use std::collections::{HashMap, VecDeque};
+
+use crate::types::{MonitorSnapshot, ProcessKey};
+
+/// Sparkline levels, lowest first
+const LEVELS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+
+/// CPU sparklines reach full height at no less than this, so idle noise stays flat
+pub const MIN_CPU_SCALE: f64 = 1.0;
+
+#[derive(Debug, Clone, Copy)]
+struct Sample {
+    cpu: f32,
+    // Resident memory in bytes
+    memory: u64,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Stats {
+    pub min: f64,
+    pub avg: f64,
+    pub max: f64,
+}
+
+pub struct ProcessHistory {
+    // Samples kept per process
+    window: usize,
+    samples: HashMap<ProcessKey, VecDeque<Sample>>,
+}
+
+impl ProcessHistory {
+    pub fn new(window: usize) -> Self {
+        Self { window: window.max(1), samples: HashMap::new() }
+    }
+
+    /// Add a sample for every process in `snapshot` and forget processes that exited
+    pub fn record(&mut self, snapshot: &MonitorSnapshot) {
+        let mut samples = HashMap::with_capacity(snapshot.processes.len());
+        for process in &snapshot.processes {
+            let key = process.key();
+            let mut history = self.samples.remove(&key).unwrap_or_default();
+            if history.len() == self.window {
+                history.pop_front();
+            }
+            history.push_back(Sample { cpu: process.cpu_usage, memory: process.memory });
+            samples.insert(key, history);
+        }
+        self.samples = samples;
+    }
+
+    pub fn clear(&mut self) {
+        self.samples.clear();
+    }
+
+    /// CPU usage samples of a process in percent, oldest first
+    pub fn cpu(&self, key: &ProcessKey) -> Vec<f64> {
+        self.values(key, |sample| f64::from(sample.cpu))
+    }
+
+    /// Resident memory samples of a process in bytes, oldest first
+    pub fn memory(&self, key: &ProcessKey) -> Vec<f64> {
+        self.values(key, |sample| sample.memory as f64)
+    }
+
+    fn values(&self, key: &ProcessKey, field: fn(&Sample) -> f64) -> Vec<f64> {
+        self.samples.get(key).map_or_else(Vec::new, |samples| samples.iter().map(field).collect())
+    }
+}
+
+impl Stats {
+    pub fn of(values: &[f64]) -> Option<Self> {
+        if values.is_empty() {
+            return None;
+        }
+        Some(Self {
+            min: values.iter().copied().fold(f64::INFINITY, f64::min),
+            avg: values.iter().sum::<f64>() / values.len() as f64,
+            max: values.iter().copied().fold(f64::NEG_INFINITY, f64::max),
+        })
+    }
+}
+
+/// The last `width` values as a sparkline scaled from zero to their maximum,
+/// right-aligned so the newest sample is always in the last column
+pub fn sparkline(values: &[f64], width: usize, min_scale: f64) -> String {
+    let shown = &values[values.len().saturating_sub(width)..];
+    let scale = shown.iter().copied().fold(min_scale, f64::max);
+    let mut line: String = std::iter::repeat_n(' ', width - shown.len()).collect();
+    line.extend(shown.iter().map(|&value| {
+        let level = if scale > 0.0 { (value / scale * (LEVELS.len() - 1) as f64).round() } else { 0.0 };
+        LEVELS[(level.max(0.0) as usize).min(LEVELS.len() - 1)]
+    }));
+    line
+}
@@ -1,26 +1,196 @@
 use std::io;
-use clap::Parser;
 #[warn(unused_variables)]
 mod args;
 mod types;
 mod monitor;
 mod utils;
 mod stealth;
+mod network;
+mod procfs;
+mod detectors;
+mod capture;
+mod delta;
+mod ui;
+mod replay;
+mod diff;
+mod tree;
+mod columns;
+mod users;
+mod filter;
+mod config;
+mod rules;
+mod redact;
+mod history;
+#[cfg(test)]
+mod fixtures;
 
-use args::Args;
+use std::path::{Path, PathBuf};
+
+use args::{Args, Command, ConfigCommand, RuleCommand};
+use columns::SortOrder;
+use config::{ConfigFormat, ConfigStore};
+use redact::Redactor;
+use rules::Rule;
 use monitor::ProcessMonitor;
 use crate::stealth::StealthManager;
 
-fn main() -> io::Result<()> {
-    let args = Args::parse();
-    if let Some(filter) = &args.filter {
-        let mut stealth_manager = StealthManager::new();
-        if let Err(e) = stealth_manager.interactive_config(){
-            eprintln!("Error configuring stealth settings : \n{}", e);
-            Err(io::Error::new(io::ErrorKind::InvalidInput, "Error configuring stealth settings"))?;
+fn main() {
+    // Display rather than Debug, so configuration errors show their source line readably
+    if let Err(e) = run() {
+        eprintln!("Error: {}", e);
+        std::process::exit(1);
+    }
+}
+
+fn run() -> io::Result<()> {
+    let args = Args::parse_checked();
+
+    let config_path = args.config.as_deref();
+
+    match args.command {
+        None => ProcessMonitor::new(load_config(config_path)?, args.monitor)?.run(),
+        Some(Command::Monitor(monitor_args)) => ProcessMonitor::new(load_config(config_path)?, monitor_args)?.run(),
+        Some(Command::Config { action }) => configure(config_path, action).map_err(|e| {
+            io::Error::other(format!("Error configuring stealth settings: {}", e))
+        }),
+        Some(Command::Snapshot { file, at, output, redact }) => {
+            let redactor = match redact {
+                Some(name) => Some(load_redactor(load_config(config_path)?.config(), &name)?),
+                None => None,
+            };
+            extract_snapshot(&file, at.as_deref(), output.as_ref(), redactor.as_ref())
+        }
+        Some(Command::Replay { file, speed, verbose, filter }) => {
+            let stealth_manager = load_config(config_path)?;
+            replay::Replayer::load(&file, speed, verbose, filter)?.run(stealth_manager.config())
+        }
+        Some(Command::Diff { from, to, json }) => {
+            let diff = diff::diff_files(&from, &to)?;
+            if json {
+                println!("{}", serde_json::to_string_pretty(&diff)?);
+            } else {
+                diff.print();
+            }
+            Ok(())
+        }
+    }
+}
+
+fn load_config(path: Option<&Path>) -> io::Result<StealthManager> {
+    StealthManager::load(path).map_err(|e| {
+        io::Error::new(io::ErrorKind::InvalidData, format!("Invalid configuration: {}", e))
+    })
+}
+
+/// Run one `config` subcommand, saving the configuration if it changed
+fn configure(path: Option<&Path>, action: Option<ConfigCommand>) -> Result<(), Box<dyn std::error::Error>> {
+    if let Some(ConfigCommand::Migrate { from, to, force }) = &action {
+        return migrate_config(from, to.as_deref(), *force);
+    }
+
+    let mut stealth_manager = StealthManager::load(path)?;
+    let Some(action) = action else {
+        return stealth_manager.interactive_config();
+    };
+
+    match action {
+        ConfigCommand::HideName { names } => names.iter().for_each(|n| stealth_manager.hide_process(n)),
+        ConfigCommand::UnhideName { names } => names.iter().for_each(|n| stealth_manager.unhide_process(n)),
+        ConfigCommand::HidePid { pids } => pids.into_iter().for_each(|p| stealth_manager.hide_pid(p)),
+        ConfigCommand::UnhidePid { pids } => pids.into_iter().for_each(|p| stealth_manager.unhide_pid(p)),
+        ConfigCommand::Rename { original, display } => stealth_manager.rename_process(&original, &display),
+        ConfigCommand::Unrename { original } => stealth_manager.remove_rename(&original),
+        ConfigCommand::Rule { action: RuleCommand::Add { pattern, field, mode, ignore_case, priority, rename } } => {
+            stealth_manager.add_rule(Rule::new(field, mode, &pattern, ignore_case, priority, rename)?);
+        }
+        ConfigCommand::Rule { action: RuleCommand::List } => {
+            for (i, rule) in stealth_manager.get_rules().iter().enumerate() {
+                println!("{}. {}", i + 1, rule);
+            }
+            return Ok(());
+        }
+        ConfigCommand::Rule { action: RuleCommand::Remove { index } } => {
+            let rule = stealth_manager.remove_rule(index).ok_or_else(|| format!("no rule {}", index))?;
+            println!("✅ Removed rule: {}", rule);
+        }
+        ConfigCommand::Columns { columns } => stealth_manager.set_columns(columns),
+        ConfigCommand::Sort { key, reverse } => stealth_manager.set_sort(SortOrder { key, reverse }),
+        ConfigCommand::List { json: false } => {
+            stealth_manager.display_current_config();
+            return Ok(());
+        }
+        ConfigCommand::List { json: true } | ConfigCommand::Export { file: None } => {
+            println!("{}", serde_json::to_string_pretty(stealth_manager.config())?);
+            return Ok(());
+        }
+        ConfigCommand::Export { file: Some(path) } => {
+            config::write_config(&path, stealth_manager.config())?;
+            eprintln!("💾 Configuration exported to: {}", path.display());
+            return Ok(());
+        }
+        ConfigCommand::Import { file, merge } => {
+            let config = if file.as_os_str() == "-" {
+                ConfigFormat::Json.parse(&io::read_to_string(io::stdin())?)
+                    .map_err(|e| format!("stdin: {}", e))?
+            } else {
+                config::read_config(&file)?
+            };
+            stealth_manager.import(config, merge);
+        }
+        ConfigCommand::Clear => stealth_manager.clear_all(),
+        ConfigCommand::Migrate { .. } => unreachable!("handled before loading"),
+    }
+
+    stealth_manager.save_config()
+}
+
+/// Convert a configuration file to the format of `to`, leaving out default settings
+fn migrate_config(from: &Path, to: Option<&Path>, force: bool) -> Result<(), Box<dyn std::error::Error>> {
+    let to = match to {
+        Some(to) => to.to_path_buf(),
+        None => config::user_config_dir()
+            .ok_or("no configuration directory (set $HOME or use --to)")?
+            .join("config.toml"),
+    };
+    if to.exists() && !force {
+        return Err(format!("{} already exists (use --force to overwrite)", to.display()).into());
+    }
+
+    let config = config::read_config(from)?;
+    ConfigStore::locate(Some(&to))?.save(&config)?;
+    println!("✅ Migrated {} to {}", from.display(), to.display());
+    Ok(())
+}
+
+/// Compile the named redaction profile of `config`
+fn load_redactor(config: &config::Config, name: &str) -> io::Result<Redactor> {
+    config.redaction_profile(name)
+        .and_then(Redactor::new)
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))
+}
+
+fn extract_snapshot(file: &Path, at: Option<&str>, output: Option<&PathBuf>, redactor: Option<&Redactor>) -> io::Result<()> {
+    let at = match at {
+        Some(at) => utils::parse_timestamp(at).ok_or_else(|| {
+            io::Error::new(io::ErrorKind::InvalidInput, format!("Invalid time '{}', expected YYYY-MM-DD HH:MM:SS", at))
+        })?,
+        None => chrono::Local::now(),
+    };
+
+    let mut snapshot = capture::snapshot_at(file, at)?.ok_or_else(|| {
+        io::Error::new(io::ErrorKind::NotFound, "No snapshot recorded at or before that time")
+    })?;
+    if let Some(redactor) = redactor {
+        redactor.redact_snapshot(&mut snapshot);
+    }
+
+    let json = serde_json::to_string_pretty(&snapshot)?;
+    match output {
+        Some(path) => {
+            std::fs::write(path, json)?;
+            eprintln!("💾 Snapshot from {} saved to: {}", snapshot.timestamp.format("%Y-%m-%d %H:%M:%S"), path.display());
         }
-        return Ok(());
+        None => println!("{}", json),
     }
-    let mut monitor = ProcessMonitor::new(args);
-    monitor.run()
-}
\ No newline at end of file
+    Ok(())
+}
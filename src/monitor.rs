@@ -1,316 +1,527 @@
-use sysinfo::{System, Networks};
-use std::collections::HashMap;
+use sysinfo::{Cpu, ProcessRefreshKind, RefreshKind, System, ThreadKind};
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
+use std::path::PathBuf;
 use std::time::{Duration, Instant};
-use std::thread;
 use chrono::Local;
 use crossterm::{
     execute,
     terminal::{Clear, ClearType},
-    cursor::{MoveTo, Hide, Show},
-    style::{Color, SetForegroundColor, ResetColor},
-    event::{self, Event, KeyCode},
+    event::{self, Event, KeyCode, KeyEventKind},
 };
 use std::io::{self, stdout};
 
-use crate::args::Args;
-use crate::types::{ProcessInfo, NetworkConnection, MonitorSnapshot, SystemSnapshot};
-use crate::utils::truncate_string;
+use crate::args::MonitorArgs;
+use crate::columns::DisplayProfile;
+use crate::config::{Config, MonitorSettings, Thresholds};
+use crate::procfs::CpuTimes;
+use crate::types::{CoreStats, CpuStats, FilteredCount, IoCounters, LoadAverage, ProcessInfo, NetworkConnection, MonitorSnapshot, SystemSnapshot, ProcessEvent, ProcessEventKind, ProcessKey};
 use crate::stealth::StealthManager;
+use crate::network;
+use crate::ui::{self, Frame, Tui, ViewMode};
+use crate::procfs;
+use crate::users::UserCache;
+use crate::filter::Filter;
+use crate::detectors::{DetectorEngine, HiddenProcessScan, ResourcePressure};
+use crate::capture::{CaptureOptions, CaptureWriter};
+use crate::redact::Redactor;
 
 pub struct ProcessMonitor {
     system: System,
-    previous_processes: HashMap<u32, ProcessInfo>,
-    args: Args,
+    previous_processes: HashMap<ProcessKey, ProcessInfo>,
+    // Configured monitor options, overridden by the command line
+    settings: MonitorSettings,
+    display: DisplayProfile,
+    thresholds: Thresholds,
     start_time: Instant,
-    snapshots: Vec<MonitorSnapshot>,
+    // Most recent snapshots, bounded by `--history`
+    snapshots: VecDeque<MonitorSnapshot>,
+    capture: Option<CaptureWriter>,
+    // Applied to everything written to disk, see `--redact`
+    redactor: Option<Redactor>,
     stealth_manager: StealthManager,
+    detectors: DetectorEngine,
+    // /proc/stat as of the previous snapshot, for CPU utilisation
+    cpu_times: Option<Vec<CpuTimes>>,
+    // Cross-view check for processes hidden from /proc, see `--hidden-scan`
+    hidden_scan: Option<HiddenProcessScan>,
+    users: UserCache,
+    // UID from `--user`, matched against the real and effective UID
+    user_filter: Option<u32>,
+    // Starts as `--filter` (or the configured one) and can be edited in the UI
+    filter: Option<Filter>,
 }
 
+const HELP: &str = concat!("q: quit | ", ui::view_keys!(), " | s: save | c: redraw | h: config menu");
+
 impl ProcessMonitor {
-    pub fn new(args: Args) -> Self {
-        let mut system = System::new_all();
-        system.refresh_all();
+    pub fn new(stealth_manager: StealthManager, args: MonitorArgs) -> io::Result<Self> {
+        let mut system = System::new_with_specifics(refresh_kind());
+        system.refresh_specifics(refresh_kind());
+
+        let mut config = stealth_manager.config().clone();
+        config.apply_args(args);
+        let redactor = match &config.output.redact {
+            Some(name) => Some(config.redaction_profile(name)
+                .and_then(Redactor::new)
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?),
+            None => None,
+        };
+        let Config { display, monitor: settings, output, thresholds, .. } = config;
+
+        let capture = match &output.path {
+            Some(output_path) => Some(CaptureWriter::create(CaptureOptions {
+                path: PathBuf::from(output_path),
+                rotate_size: output.rotate_size.map(|mb| mb * 1_048_576),
+                rotate_interval: output.rotate_interval.map(Duration::from_secs),
+                gzip: output.gzip || output_path.ends_with(".gz"),
+                keyframe_interval: output.delta.then_some(output.keyframe_interval),
+            })?),
+            None => None,
+        };
+
+        let users = UserCache::new();
+        let user_filter = match &settings.user {
+            Some(user) => Some(users.resolve_user(user).ok_or_else(|| {
+                io::Error::new(io::ErrorKind::InvalidInput, format!("Unknown user '{}'", user))
+            })?),
+            None => None,
+        };
 
-        Self {
+        // Pressure thresholds come from the configuration, so this one is not a builtin
+        let mut detectors = DetectorEngine::with_builtins();
+        detectors.register(Box::new(ResourcePressure::new(thresholds)));
+
+        Ok(Self {
             system,
             previous_processes: HashMap::new(),
-            args,
+            snapshots: VecDeque::with_capacity(settings.history),
+            capture,
+            redactor,
             start_time: Instant::now(),
-            snapshots: Vec::new(),
-            stealth_manager: StealthManager::new(),
-        }
+            stealth_manager,
+            detectors,
+            cpu_times: procfs::cpu_times(),
+            hidden_scan: settings.hidden_scan.map(|seconds| HiddenProcessScan::new(Duration::from_secs(seconds))),
+            users,
+            user_filter,
+            filter: settings.filter.clone(),
+            settings,
+            display,
+            thresholds,
+        })
     }
 
     pub fn run(&mut self) -> io::Result<()> {
-        println!("🔍 RProcMon - Rust Process Monitor (Stealth Mode Active)");
-        println!("Press 'q' to quit, 's' to save snapshot, 'h' to toggle stealth config\n");
+        let mut tui = Tui::enter(self.settings.verbose, self.settings.alert, self.display.clone(), self.thresholds)?;
+        if self.settings.tree {
+            tui.view.mode = ViewMode::Tree;
+        }
+        tui.set_filter(self.filter.as_ref());
 
-        // Hide cursor for cleaner output
-        execute!(stdout(), Hide)?;
+        let interval = Duration::from_secs(self.settings.interval);
+        let mut next_refresh = Instant::now();
+        let mut redraw = false;
 
+        // Input is handled as it arrives; refreshes happen whenever the interval has passed
         loop {
-            self.system.refresh_all();
-
-            let snapshot = self.collect_snapshot();
-            self.display_processes(&snapshot);
+            if Instant::now() >= next_refresh {
+                self.refresh()?;
+                if let Some(latest) = self.snapshots.back() {
+                    tui.record_events(latest);
+                    tui.record_history(latest);
+                }
+                next_refresh = Instant::now() + interval;
+                redraw = true;
+            }
 
-            if self.args.network {
-                self.display_network_connections(&snapshot);
+            if redraw && let Some(latest) = self.snapshots.back() {
+                tui.draw(&Frame {
+                    snapshot: latest,
+                    status: self.status_line(),
+                    help: HELP,
+                })?;
+                redraw = false;
             }
 
-            self.check_for_new_processes(&snapshot);
-            self.snapshots.push(snapshot);
+            if !event::poll(next_refresh.saturating_duration_since(Instant::now()))? {
+                continue;
+            }
 
-            // Check for user input
-            if event::poll(Duration::from_millis(100))? {
-                if let Event::Key(key) = event::read()? {
+            match event::read()? {
+                Event::Key(key) if key.kind == KeyEventKind::Press && tui.is_editing() => {
+                    if let Some(latest) = self.snapshots.back() {
+                        tui.handle_key(key, latest);
+                    }
+                    if let Some(filter) = tui.take_filter_change() {
+                        self.filter = filter;
+                        // Processes entering or leaving the filter did not start or exit
+                        self.previous_processes.clear();
+                        next_refresh = Instant::now();
+                    }
+                    redraw = true;
+                }
+                Event::Key(key) if key.kind == KeyEventKind::Press => {
+                    if ui::is_quit(&key) {
+                        break;
+                    }
                     match key.code {
-                        KeyCode::Char('q') => break,
-                        KeyCode::Char('s') => self.save_current_snapshot()?,
+                        KeyCode::Char('s') => match self.save_current_snapshot() {
+                            Ok(Some(filename)) => tui.set_message(format!("💾 Snapshot saved to: {}", filename)),
+                            Ok(None) => {}
+                            Err(e) => tui.set_message(format!("Error saving snapshot: {}", e)),
+                        },
                         KeyCode::Char('c') => {
                             execute!(stdout(), Clear(ClearType::All))?;
                         }
                         KeyCode::Char('h') => {
-                            execute!(stdout(), Show)?;
+                            tui.suspend()?;
                             if let Err(e) = self.stealth_manager.interactive_config() {
                                 println!("Error configuring stealth: {}", e);
                             }
-                            execute!(stdout(), Hide)?;
+                            tui.resume()?;
+                            // Apply the new configuration right away
+                            next_refresh = Instant::now();
+                        }
+                        _ => {
+                            if let Some(latest) = self.snapshots.back() {
+                                tui.handle_key(key, latest);
+                            }
                         }
-                        _ => {}
                     }
+                    redraw = true;
+                }
+                Event::Resize(..) => {
+                    execute!(stdout(), Clear(ClearType::All))?;
+                    redraw = true;
                 }
+                _ => {}
             }
+        }
 
-            thread::sleep(Duration::from_secs(self.args.interval));
+        drop(tui);
 
-            // Clear screen for next update
-            execute!(stdout(), Clear(ClearType::All), MoveTo(0, 0))?;
+        if let Some(capture) = self.capture.take() {
+            let path = capture.current_path().display().to_string();
+            capture.finish()?;
+            println!("💾 Snapshots streamed to: {}", path);
         }
 
-        execute!(stdout(), Show)?;
+        Ok(())
+    }
+
+    /// Collect a new snapshot, run the detectors on it and record it
+    fn refresh(&mut self) -> io::Result<()> {
+        let listed_at = procfs::uptime_ticks();
+        self.system.refresh_specifics(refresh_kind());
 
-        if let Some(output_path) = &self.args.output {
-            self.save_all_snapshots(output_path)?;
+        let mut snapshot = self.collect_snapshot();
+        if let Some(previous) = self.snapshots.back() {
+            io_rates(previous, &mut snapshot);
+        }
+        snapshot.events = process_events(&self.previous_processes, &snapshot);
+        snapshot.findings = self.detectors.evaluate(&snapshot, self.snapshots.back());
+
+        if let Some(scan) = &mut self.hidden_scan {
+            // Every PID sysinfo listed, including the ones stealth rules hide
+            let listed: HashSet<u32> = self.system.processes().keys().map(|pid| pid.as_u32()).collect();
+            snapshot.findings.extend_from_slice(scan.findings(&listed, listed_at));
+            snapshot.findings.sort_by_key(|f| std::cmp::Reverse(f.severity));
         }
 
-        Ok(())
+        self.update_previous_processes(&snapshot);
+        self.record_snapshot(snapshot)
     }
 
     fn collect_snapshot(&mut self) -> MonitorSnapshot {
         let mut processes = Vec::new();
+        let timestamp = Local::now();
+        let now = timestamp.timestamp().max(0) as u64;
+        self.users.refresh();
         let mut network_connections = Vec::new();
 
+        // Processes left out per rule, so captures say what they exclude
+        let mut filtered: BTreeMap<String, usize> = BTreeMap::new();
+
+        // Start ticks of the processes already seen, so a failed read of /proc/<pid>/stat
+        // cannot change a process's key halfway through its life
+        let known_ticks: HashMap<(u32, u64), u64> = self.previous_processes.values()
+            .map(|p| ((p.pid, p.start_time), p.start_ticks))
+            .collect();
+
         // Collect process information with stealth filtering
         for (pid, process) in self.system.processes() {
-            let original_name = process.name().to_string_lossy().to_string();
-
-            // Apply stealth filtering - skip hidden processes
-            if self.stealth_manager.is_process_hidden(&original_name) ||
-                self.stealth_manager.is_pid_hidden(pid.as_u32()) {
+            // Hidden PIDs are skipped before reading anything else about them
+            if !self.settings.show_filtered && self.stealth_manager.is_pid_hidden(pid.as_u32()) {
+                if !matches!(process.thread_kind(), Some(ThreadKind::Userland)) {
+                    *filtered.entry(format!("hidden pid {}", pid)).or_default() += 1;
+                }
                 continue;
             }
-
-            // Get display name (potentially renamed)
-            let display_name = self.stealth_manager.get_display_name(&original_name);
-
-            let process_info = ProcessInfo {
+            // /proc has the saved IDs too; sysinfo covers the other platforms
+            let credentials = procfs::credentials(pid.as_u32());
+            let user_id = credentials.map(|c| c.uid).or_else(|| process.user_id().map(|u| **u));
+            let group_id = credentials.map(|c| c.gid).or_else(|| process.group_id().map(|g| *g));
+            let is_thread = matches!(process.thread_kind(), Some(ThreadKind::Userland));
+
+            let name = process.name().to_string_lossy().to_string();
+            let mut process_info = ProcessInfo {
                 pid: pid.as_u32(),
-                name: display_name, // Use display name instead of original
+                comm: name.clone(),
+                name,
                 cmd: process.cmd().iter().map(|s| s.to_string_lossy().to_string()).collect(),
                 cpu_usage: process.cpu_usage(),
                 memory: process.memory(),
                 parent_pid: process.parent().map(|p| p.as_u32()),
                 start_time: process.start_time(),
-                user_id: process.user_id().map(|u| u.to_string().parse().unwrap_or(0)),
+                start_ticks: known_ticks.get(&(pid.as_u32(), process.start_time())).copied()
+                    .unwrap_or_else(|| procfs::start_ticks(pid.as_u32()).unwrap_or(0)),
+                user_id,
+                effective_user_id: credentials.map(|c| c.euid)
+                    .or_else(|| process.effective_user_id().map(|u| **u)),
+                saved_user_id: credentials.map(|c| c.suid),
+                group_id,
+                effective_group_id: credentials.map(|c| c.egid)
+                    .or_else(|| process.effective_group_id().map(|g| *g)),
+                user_name: user_id.and_then(|uid| self.users.user_name(uid)).map(str::to_string),
+                group_name: group_id.and_then(|gid| self.users.group_name(gid)).map(str::to_string),
                 status: format!("{:?}", process.status()),
                 exe_path: process.exe().map(|p| p.to_string_lossy().to_string()),
+                exe_deleted: procfs::exe_deleted(pid.as_u32()),
+                is_thread,
+                // The task list leaves out the main thread
+                threads: process.tasks().map_or(0, |tasks| tasks.len() + 1),
+                // A thread's io file covers its whole thread group
+                io: if is_thread { None } else { procfs::io_counters(pid.as_u32()) },
+                io_rates: None,
+                filtered_by: None,
             };
 
-            // Apply original filter if specified (but not if it's stealth-config)
-            if let Some(filter) = &self.args.filter {
-                if filter != "stealth-config" &&
-                    !process_info.name.to_lowercase().contains(&filter.to_lowercase()) {
+            // Apply stealth filtering - rules may look at the exe, command line or user
+            let mut filtered_by = self.stealth_manager.hidden_by(&process_info);
+            if filtered_by.is_none() {
+                // Use display name (potentially renamed) instead of the original
+                process_info.name = self.stealth_manager.get_display_name(&process_info);
+
+                if let Some(filter) = &self.filter
+                    && !filter.matches(&process_info, now) {
+                    filtered_by = Some(format!("filter: {}", filter));
+                } else if let Some(uid) = self.user_filter
+                    && process_info.user_id != Some(uid)
+                    && process_info.effective_user_id != Some(uid) {
+                    filtered_by = Some(format!("user: {}", uid));
+                }
+            }
+
+            if let Some(rule) = filtered_by {
+                if !process_info.is_thread {
+                    *filtered.entry(rule.clone()).or_default() += 1;
+                }
+                if !self.settings.show_filtered {
                     continue;
                 }
+                process_info.filtered_by = Some(rule);
             }
 
             processes.push(process_info);
         }
 
         // Collect network information if requested
-        if self.args.network {
-            let networks = Networks::new_with_refreshed_list();
-            for (interface_name, network) in &networks {
-                if network.received() > 0 || network.transmitted() > 0 {
-                    let conn = NetworkConnection {
-                        process_name: format!("Interface: {}", interface_name),
-                        pid: 0,
-                        local_addr: "0.0.0.0".to_string(),
-                        remote_addr: "0.0.0.0".to_string(),
-                        state: "ACTIVE".to_string(),
-                        protocol: "TCP/UDP".to_string(),
-                    };
-                    network_connections.push(conn);
-                }
-            }
+        if self.settings.network {
+            network_connections = self.collect_network_connections(&processes);
         }
 
+        // Elsewhere sysinfo has the averages but not the task counts
+        let load = procfs::load_average().or_else(|| {
+            let load = System::load_average();
+            Some(LoadAverage { one: load.one, five: load.five, fifteen: load.fifteen, runnable: 0, tasks: 0 })
+        });
+        let cpu_times = procfs::cpu_times();
+        let cpu = self.cpu_times.as_deref().zip(cpu_times.as_deref())
+            .map(|(earlier, now)| cpu_stats(earlier, now, self.system.cpus()));
+        self.cpu_times = cpu_times;
         let system_info = SystemSnapshot {
             total_memory: self.system.total_memory(),
             used_memory: self.system.used_memory(),
             cpu_count: self.system.cpus().len(),
-            load_average: 0.0,
             uptime: System::uptime(),
+            load,
+            pressure: procfs::pressure(),
+            cpu,
         };
 
         MonitorSnapshot {
-            timestamp: Local::now(),
+            timestamp,
             processes,
             network_connections,
             system_info,
+            findings: Vec::new(),
+            events: Vec::new(),
+            filtered: filtered.into_iter().map(|(rule, count)| FilteredCount { rule, count }).collect(),
         }
     }
 
-    fn display_processes(&self, snapshot: &MonitorSnapshot) {
-        let monitor_uptime = self.start_time.elapsed().as_secs();
-
-        // Show stealth status
-        let hidden_count = self.stealth_manager.get_hidden_processes().len() +
-            self.stealth_manager.get_hidden_pids().len();
-        let renamed_count = self.stealth_manager.get_rename_mappings().len();
-
-        println!("📊 System Overview [{}] 🥷 Hidden: {} | Renamed: {}",
-                 snapshot.timestamp.format("%Y-%m-%d %H:%M:%S"),
-                 hidden_count,
-                 renamed_count);
-
-        println!("Memory: {:.1}% ({}/{} MB) | CPUs: {} | Uptime: {}s | Monitor: {}s | Processes: {}",
-                 (snapshot.system_info.used_memory as f64 / snapshot.system_info.total_memory as f64) * 100.0,
-                 snapshot.system_info.used_memory / 1_048_576,
-                 snapshot.system_info.total_memory / 1_048_576,
-                 snapshot.system_info.cpu_count,
-                 snapshot.system_info.uptime,
-                 monitor_uptime,
-                 snapshot.processes.len()
-        );
-        println!("{}", "─".repeat(120));
-
-        // Header
-        println!("{:<8} {:<25} {:<8} {:<12} {:<8} {:<10} {:<20}",
-                 "PID", "NAME", "CPU%", "MEMORY(KB)", "PPID", "USER_ID", "STATUS");
-        println!("{}", "─".repeat(120));
-
-        // Sort processes by CPU usage
-        let mut sorted_processes = snapshot.processes.clone();
-        sorted_processes.sort_by(|a, b| b.cpu_usage.partial_cmp(&a.cpu_usage).unwrap_or(std::cmp::Ordering::Equal));
-
-        // Display top processes
-        for (_, process) in sorted_processes.iter().take(20).enumerate() {
-            // Highlight high CPU usage
-            if process.cpu_usage > 50.0 {
-                execute!(stdout(), SetForegroundColor(Color::Red)).unwrap();
-            } else if process.cpu_usage > 25.0 {
-                execute!(stdout(), SetForegroundColor(Color::Yellow)).unwrap();
-            }
+    /// Stealth counts and monitor runtime shown in the overview header
+    fn status_line(&self) -> String {
+        let rules = self.stealth_manager.get_rules();
+        let hide_rules = rules.iter().filter(|r| r.is_hide()).count();
+        let hidden_count = self.stealth_manager.get_hidden_pids().len() + hide_rules;
+        let renamed_count = self.stealth_manager.get_rename_mappings().len() + rules.len() - hide_rules;
+
+        format!("🥷 Hidden: {} | Renamed: {} | Monitor: {}s",
+                hidden_count,
+                renamed_count,
+                self.start_time.elapsed().as_secs())
+    }
 
-            println!("{:<8} {:<25} {:<8.1} {:<12} {:<8} {:<10} {:<20}",
-                     process.pid,
-                     truncate_string(&process.name, 25),
-                     process.cpu_usage,
-                     process.memory / 1024,
-                     process.parent_pid.map_or("-".to_string(), |p| p.to_string()),
-                     process.user_id.map_or("-".to_string(), |u| u.to_string()),
-                     truncate_string(&process.status, 20)
-            );
-
-            execute!(stdout(), ResetColor).unwrap();
-
-            if self.args.verbose && !process.cmd.is_empty() {
-                println!("    CMD: {}", process.cmd.join(" "));
-                if let Some(exe_path) = &process.exe_path {
-                    println!("    EXE: {}", exe_path);
+    /// Build the socket table and attribute each socket to its owning process
+    fn collect_network_connections(&self, processes: &[ProcessInfo]) -> Vec<NetworkConnection> {
+        let owners = network::socket_inode_owners();
+        let visible: HashMap<u32, &str> = processes.iter()
+            .map(|p| (p.pid, p.name.as_str()))
+            .collect();
+
+        let mut connections = Vec::new();
+        for socket in network::read_socket_table() {
+            match owners.get(&socket.inode) {
+                Some(pids) => {
+                    // Sockets of hidden or filtered-out processes follow their owners; a
+                    // socket shared with a visible process is shown under that one
+                    if let Some((pid, name)) = pids.iter().find_map(|pid| Some((*pid, *visible.get(pid)?))) {
+                        connections.push(socket.into_connection(pid, name.to_string()));
+                    }
+                }
+                None => {
+                    // Owner unknown (e.g. another user's process without root); only
+                    // shown when no process filter is narrowing the view
+                    if self.filter.is_none() && self.user_filter.is_none() {
+                        connections.push(socket.into_connection(0, "-".to_string()));
+                    }
                 }
             }
         }
 
-        println!();
+        connections
     }
 
-    fn display_network_connections(&self, snapshot: &MonitorSnapshot) {
-        if !snapshot.network_connections.is_empty() {
-            println!("🌐 Network Activity");
-            println!("{}", "─".repeat(80));
-
-            for conn in &snapshot.network_connections {
-                println!("{:<20} {:<8} {:<20} -> {:<20} [{}]",
-                         truncate_string(&conn.process_name, 20),
-                         conn.pid,
-                         conn.local_addr,
-                         conn.remote_addr,
-                         conn.state
-                );
-            }
-            println!();
+    fn update_previous_processes(&mut self, snapshot: &MonitorSnapshot) {
+        self.previous_processes.clear();
+        for process in &snapshot.processes {
+            self.previous_processes.insert(process.key(), process.clone());
         }
     }
 
-    fn check_for_new_processes(&mut self, snapshot: &MonitorSnapshot) {
-        if self.args.alert {
-            let current_pids: std::collections::HashSet<u32> =
-                snapshot.processes.iter().map(|p| p.pid).collect();
-            let previous_pids: std::collections::HashSet<u32> =
-                self.previous_processes.keys().cloned().collect();
-
-            let new_pids: Vec<u32> = current_pids.difference(&previous_pids).cloned().collect();
-
-            if !new_pids.is_empty() && !self.previous_processes.is_empty() {
-                execute!(stdout(), SetForegroundColor(Color::Green)).unwrap();
-                println!("🚨 NEW PROCESSES DETECTED:");
-                for pid in new_pids {
-                    if let Some(process) = snapshot.processes.iter().find(|p| p.pid == pid) {
-                        println!("  [{}] {} (PID: {})",
-                                 snapshot.timestamp.format("%H:%M:%S"),
-                                 process.name,
-                                 process.pid
-                        );
-                        if self.args.verbose {
-                            println!("    CMD: {}", process.cmd.join(" "));
-                            if let Some(exe_path) = &process.exe_path {
-                                println!("    EXE: {}", exe_path);
-                            }
-                        }
-                    }
+    /// Stream the snapshot to the capture file and keep it in the bounded history
+    fn record_snapshot(&mut self, snapshot: MonitorSnapshot) -> io::Result<()> {
+        if let Some(capture) = &mut self.capture {
+            match &self.redactor {
+                Some(redactor) => {
+                    let mut redacted = snapshot.clone();
+                    redactor.redact_snapshot(&mut redacted);
+                    capture.write_snapshot(&redacted)?;
                 }
-                execute!(stdout(), ResetColor).unwrap();
-                println!();
+                None => capture.write_snapshot(&snapshot)?,
             }
         }
 
-        // Update previous processes
-        self.previous_processes.clear();
-        for process in &snapshot.processes {
-            self.previous_processes.insert(process.pid, process.clone());
+        // Detectors compare against the latest snapshot, so always keep at least one
+        while self.snapshots.len() >= self.settings.history.max(1) {
+            self.snapshots.pop_front();
         }
+        self.snapshots.push_back(snapshot);
+        Ok(())
     }
 
-    fn save_current_snapshot(&self) -> io::Result<()> {
-        if let Some(latest) = self.snapshots.last() {
-            let filename = format!("rprocmon_snapshot_{}.json",
-                                   latest.timestamp.format("%Y%m%d_%H%M%S"));
+    /// Write the latest snapshot to a timestamped JSON file, returning its name
+    fn save_current_snapshot(&self) -> io::Result<Option<String>> {
+        let Some(latest) = self.snapshots.back() else {
+            return Ok(None);
+        };
+        let filename = format!("rprocmon_snapshot_{}.json",
+                               latest.timestamp.format("%Y%m%d_%H%M%S"));
+
+        let json = match &self.redactor {
+            Some(redactor) => {
+                let mut redacted = latest.clone();
+                redactor.redact_snapshot(&mut redacted);
+                serde_json::to_string_pretty(&redacted)?
+            }
+            None => serde_json::to_string_pretty(latest)?,
+        };
+        std::fs::write(&filename, json)?;
+        Ok(Some(filename))
+    }
+}
 
-            let json = serde_json::to_string_pretty(latest)?;
-            std::fs::write(&filename, json)?;
+/// Everything sysinfo can refresh except per-process disk usage, which
+/// `/proc/<pid>/io` already provides
+fn refresh_kind() -> RefreshKind {
+    RefreshKind::everything().with_processes(ProcessRefreshKind::everything().without_disk_usage())
+}
 
-            println!("💾 Snapshot saved to: {}", filename);
-        }
-        Ok(())
+/// Compare against the previous refresh to find processes that started or exited
+pub fn process_events(previous: &HashMap<ProcessKey, ProcessInfo>, snapshot: &MonitorSnapshot) -> Vec<ProcessEvent> {
+    // Everything would look new on the first refresh
+    if previous.is_empty() {
+        return Vec::new();
     }
 
-    fn save_all_snapshots(&self, output_path: &str) -> io::Result<()> {
-        let json = serde_json::to_string_pretty(&self.snapshots)?;
-        std::fs::write(output_path, json)?;
-        println!("💾 All snapshots saved to: {}", output_path);
-        Ok(())
+    // Keyed by PID and start time, so a recycled PID shows up as an exit plus a start
+    let current_keys: HashSet<ProcessKey> = snapshot.processes.iter().map(|p| p.key()).collect();
+    let now = snapshot.timestamp.timestamp().max(0) as u64;
+
+    // Threads come and go constantly; only whole processes are reported
+    let started = snapshot.processes.iter()
+        .filter(|p| !p.is_thread && !previous.contains_key(&p.key()))
+        .map(|p| ProcessEvent {
+            kind: ProcessEventKind::Started,
+            process: p.clone(),
+            lifetime: None,
+        });
+
+    let exited = previous.values()
+        .filter(|p| !p.is_thread && !current_keys.contains(&p.key()))
+        .map(|p| ProcessEvent {
+            kind: ProcessEventKind::Exited,
+            process: p.clone(),
+            lifetime: Some(now.saturating_sub(p.start_time)),
+        });
+
+    started.chain(exited).collect()
+}
+
+/// Utilisation of every core between two readings of `/proc/stat`
+fn cpu_stats(earlier: &[CpuTimes], now: &[CpuTimes], cpus: &[Cpu]) -> CpuStats {
+    let earlier: HashMap<Option<u32>, &CpuTimes> = earlier.iter().map(|t| (t.id, t)).collect();
+    let usage = |times: &CpuTimes| earlier.get(&times.id).map(|before| times.usage_since(before));
+
+    let total = now.iter()
+        .find(|t| t.id.is_none())
+        .and_then(usage)
+        .unwrap_or_default();
+    let cores = now.iter()
+        .filter_map(|times| {
+            let id = times.id?;
+            let frequency = cpus.iter()
+                .find(|cpu| cpu.name() == format!("cpu{}", id))
+                .map_or(0, |cpu| cpu.frequency());
+            Some(CoreStats { id, usage: usage(times)?, frequency })
+        })
+        .collect();
+    CpuStats { total, cores }
+}
+
+/// Fill in per-second I/O rates from the counters in the previous snapshot
+fn io_rates(previous: &MonitorSnapshot, snapshot: &mut MonitorSnapshot) {
+    let seconds = (snapshot.timestamp - previous.timestamp).as_seconds_f64();
+    if seconds <= 0.0 {
+        return;
+    }
+    let counters: HashMap<ProcessKey, IoCounters> = previous.processes.iter()
+        .filter_map(|p| Some((p.key(), p.io?)))
+        .collect();
+    for process in &mut snapshot.processes {
+        if let (Some(io), Some(earlier)) = (&process.io, counters.get(&process.key())) {
+            process.io_rates = Some(io.rates_since(earlier, seconds));
+        }
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,360 @@
+use std::collections::HashMap;
+
+use crate::types::NetworkConnection;
+
+/// A socket parsed from one of the `/proc/net` tables, before it is matched to a process
+#[derive(Debug, Clone)]
+pub struct SocketEntry {
+    pub protocol: &'static str,
+    pub local_addr: String,
+    pub local_port: u16,
+    pub remote_addr: String,
+    pub remote_port: u16,
+    pub state: String,
+    pub inode: u64,
+}
+
+impl SocketEntry {
+    /// Attach the owning process; `pid` 0 means the inode could not be resolved
+    pub fn into_connection(self, pid: u32, process_name: String) -> NetworkConnection {
+        NetworkConnection {
+            process_name,
+            pid,
+            local_addr: self.local_addr,
+            local_port: self.local_port,
+            remote_addr: self.remote_addr,
+            remote_port: self.remote_port,
+            state: self.state,
+            protocol: self.protocol.to_string(),
+            inode: self.inode,
+        }
+    }
+}
+
+/// Read every socket from `/proc/net/{tcp,tcp6,udp,udp6,unix}`
+#[cfg(target_os = "linux")]
+pub fn read_socket_table() -> Vec<SocketEntry> {
+    let mut sockets = Vec::new();
+
+    for (protocol, path) in [
+        ("tcp", "/proc/net/tcp"),
+        ("tcp6", "/proc/net/tcp6"),
+        ("udp", "/proc/net/udp"),
+        ("udp6", "/proc/net/udp6"),
+    ] {
+        if let Ok(content) = std::fs::read_to_string(path) {
+            sockets.extend(content.lines().skip(1).filter_map(|line| parse_inet_line(protocol, line)));
+        }
+    }
+
+    if let Ok(content) = std::fs::read_to_string("/proc/net/unix") {
+        sockets.extend(content.lines().skip(1).filter_map(parse_unix_line));
+    }
+
+    sockets
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn read_socket_table() -> Vec<SocketEntry> {
+    Vec::new()
+}
+
+/// Map socket inodes to the PIDs holding them open, lowest first, by scanning `/proc/<pid>/fd`
+///
+/// A socket inherited across fork or passed over another socket has several owners.
+#[cfg(target_os = "linux")]
+pub fn socket_inode_owners() -> HashMap<u64, Vec<u32>> {
+    let mut owners = HashMap::new();
+
+    let Ok(proc_dir) = std::fs::read_dir("/proc") else {
+        return owners;
+    };
+
+    for entry in proc_dir.flatten() {
+        let Some(pid) = entry.file_name().to_str().and_then(|s| s.parse::<u32>().ok()) else {
+            continue;
+        };
+
+        // Unreadable for other users' processes unless running as root
+        let Ok(fds) = std::fs::read_dir(entry.path().join("fd")) else {
+            continue;
+        };
+
+        for fd in fds.flatten() {
+            if let Ok(target) = std::fs::read_link(fd.path())
+                && let Some(inode) = parse_socket_link(&target.to_string_lossy())
+            {
+                let pids: &mut Vec<u32> = owners.entry(inode).or_default();
+                if !pids.contains(&pid) {
+                    pids.push(pid);
+                }
+            }
+        }
+    }
+
+    for pids in owners.values_mut() {
+        pids.sort_unstable();
+    }
+    owners
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn socket_inode_owners() -> HashMap<u64, Vec<u32>> {
+    HashMap::new()
+}
+
+/// Extract the inode from an fd link of the form `socket:[12345]`
+fn parse_socket_link(link: &str) -> Option<u64> {
+    link.strip_prefix("socket:[")?.strip_suffix(']')?.parse().ok()
+}
+
+/// Parse a row of `/proc/net/tcp*` or `/proc/net/udp*`
+///
+/// Format: `sl local_address rem_address st tx_queue:rx_queue tr:tm->when retrnsmt uid timeout inode ...`
+fn parse_inet_line(protocol: &'static str, line: &str) -> Option<SocketEntry> {
+    let fields: Vec<&str> = line.split_whitespace().collect();
+    if fields.len() < 10 {
+        return None;
+    }
+
+    let (local_addr, local_port) = parse_hex_endpoint(fields[1])?;
+    let (remote_addr, remote_port) = parse_hex_endpoint(fields[2])?;
+    let state_code = u8::from_str_radix(fields[3], 16).ok()?;
+    let inode = fields[9].parse().ok()?;
+
+    let state = if protocol.starts_with("udp") {
+        udp_state_name(state_code)
+    } else {
+        tcp_state_name(state_code)
+    };
+
+    Some(SocketEntry {
+        protocol,
+        local_addr,
+        local_port,
+        remote_addr,
+        remote_port,
+        state: state.to_string(),
+        inode,
+    })
+}
+
+/// Parse a row of `/proc/net/unix`
+///
+/// Format: `Num RefCount Protocol Flags Type St Inode [Path]`; the path may contain spaces
+fn parse_unix_line(line: &str) -> Option<SocketEntry> {
+    let fields: Vec<&str> = line.split_whitespace().collect();
+    if fields.len() < 7 {
+        return None;
+    }
+
+    let flags = u32::from_str_radix(fields[3], 16).ok()?;
+    let socket_type = u16::from_str_radix(fields[4], 16).ok()?;
+    let state_code = u8::from_str_radix(fields[5], 16).ok()?;
+    let inode = fields[6].parse().ok()?;
+    // Abstract socket names start with '@'; unnamed sockets have no path at all
+    let path = after_fields(line, 7).to_string();
+
+    // __SO_ACCEPTCON marks a listening socket
+    let state = if flags & 0x0001_0000 != 0 {
+        "LISTEN"
+    } else {
+        unix_state_name(state_code)
+    };
+
+    let protocol = match socket_type {
+        1 => "unix_stream",
+        2 => "unix_dgram",
+        5 => "unix_seqpacket",
+        _ => "unix",
+    };
+
+    Some(SocketEntry {
+        protocol,
+        local_addr: path,
+        local_port: 0,
+        remote_addr: String::new(),
+        remote_port: 0,
+        state: state.to_string(),
+        inode,
+    })
+}
+
+/// The rest of `line` after its first `count` whitespace-separated fields and the space after them
+fn after_fields(line: &str, count: usize) -> &str {
+    let mut rest = line;
+    for _ in 0..count {
+        rest = rest.trim_start();
+        rest = &rest[rest.find(char::is_whitespace).unwrap_or(rest.len())..];
+    }
+    rest.strip_prefix(' ').unwrap_or(rest)
+}
+
+/// Decode `0100007F:0035` (IPv4) or the 32-hex-digit IPv6 form into an address and port
+///
+/// The kernel prints each 32-bit word of the address in host byte order.
+fn parse_hex_endpoint(endpoint: &str) -> Option<(String, u16)> {
+    let (addr_hex, port_hex) = endpoint.split_once(':')?;
+    let port = u16::from_str_radix(port_hex, 16).ok()?;
+
+    let addr = match addr_hex.len() {
+        8 => {
+            let word = u32::from_str_radix(addr_hex, 16).ok()?;
+            std::net::Ipv4Addr::from(word.to_ne_bytes()).to_string()
+        }
+        32 => {
+            let mut bytes = [0u8; 16];
+            for (i, chunk) in bytes.chunks_mut(4).enumerate() {
+                let word = u32::from_str_radix(&addr_hex[i * 8..(i + 1) * 8], 16).ok()?;
+                chunk.copy_from_slice(&word.to_ne_bytes());
+            }
+            let v6 = std::net::Ipv6Addr::from(bytes);
+            match v6.to_ipv4_mapped() {
+                Some(v4) => v4.to_string(),
+                None => v6.to_string(),
+            }
+        }
+        _ => return None,
+    };
+
+    Some((addr, port))
+}
+
+fn tcp_state_name(code: u8) -> &'static str {
+    match code {
+        0x01 => "ESTABLISHED",
+        0x02 => "SYN_SENT",
+        0x03 => "SYN_RECV",
+        0x04 => "FIN_WAIT1",
+        0x05 => "FIN_WAIT2",
+        0x06 => "TIME_WAIT",
+        0x07 => "CLOSE",
+        0x08 => "CLOSE_WAIT",
+        0x09 => "LAST_ACK",
+        0x0A => "LISTEN",
+        0x0B => "CLOSING",
+        0x0C => "NEW_SYN_RECV",
+        _ => "UNKNOWN",
+    }
+}
+
+fn udp_state_name(code: u8) -> &'static str {
+    match code {
+        0x01 => "ESTABLISHED",
+        0x07 => "UNCONN",
+        _ => "UNKNOWN",
+    }
+}
+
+fn unix_state_name(code: u8) -> &'static str {
+    match code {
+        0x01 => "UNCONNECTED",
+        0x02 => "CONNECTING",
+        0x03 => "CONNECTED",
+        0x04 => "DISCONNECTING",
+        _ => "UNKNOWN",
+    }
+}
+
+/// Format an endpoint for display, bracketing IPv6 addresses
+pub fn format_endpoint(addr: &str, port: u16) -> String {
+    if addr.contains(':') {
+        format!("[{}]:{}", addr, port)
+    } else {
+        format!("{}:{}", addr, port)
+    }
+}
+
+/// Local and remote columns for a connection; Unix sockets show their path instead
+pub fn connection_endpoints(conn: &NetworkConnection) -> (String, String) {
+    if conn.protocol.starts_with("unix") {
+        let path = if conn.local_addr.is_empty() { "-" } else { conn.local_addr.as_str() };
+        (path.to_string(), "-".to_string())
+    } else {
+        (format_endpoint(&conn.local_addr, conn.local_port),
+         format_endpoint(&conn.remote_addr, conn.remote_port))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TCP_LISTEN: &str = "   0: 0100007F:0277 00000000:0000 0A 00000000:00000000 00:00000000 00000000     0        0 12345 1 0000000000000000 100 0 0 10 0";
+    const TCP_ESTABLISHED: &str = "   1: 0F02000A:C350 5DB8D822:01BB 01 00000000:00000000 02:000A3C52 00000000  1000        0 23456 2 0000000000000000 20 4 30 10 -1";
+
+    #[test]
+    #[cfg(target_endian = "little")]
+    fn ipv4_endpoints_are_read_in_host_byte_order() {
+        assert_eq!(parse_hex_endpoint("0100007F:0035"), Some(("127.0.0.1".to_string(), 53)));
+        assert_eq!(parse_hex_endpoint("5DB8D822:01BB"), Some(("34.216.184.93".to_string(), 443)));
+        assert_eq!(parse_hex_endpoint("00000000:0000"), Some(("0.0.0.0".to_string(), 0)));
+    }
+
+    #[test]
+    #[cfg(target_endian = "little")]
+    fn ipv6_endpoints_are_read_word_by_word() {
+        assert_eq!(parse_hex_endpoint("00000000000000000000000001000000:0016"), Some(("::1".to_string(), 22)));
+        assert_eq!(parse_hex_endpoint("B80D0120000000000000000001000000:01BB"),
+                   Some(("2001:db8::1".to_string(), 443)));
+        // IPv4-mapped addresses are shown as plain IPv4
+        assert_eq!(parse_hex_endpoint("0000000000000000FFFF00000100007F:1F90"),
+                   Some(("127.0.0.1".to_string(), 8080)));
+    }
+
+    #[test]
+    fn malformed_endpoints_are_rejected() {
+        assert_eq!(parse_hex_endpoint("0100007F"), None);
+        assert_eq!(parse_hex_endpoint("0100007:0035"), None);
+        assert_eq!(parse_hex_endpoint("0100007G:0035"), None);
+        assert_eq!(parse_hex_endpoint("0100007F:10000"), None);
+    }
+
+    #[test]
+    #[cfg(target_endian = "little")]
+    fn inet_lines() {
+        let listen = parse_inet_line("tcp", TCP_LISTEN).unwrap();
+        assert_eq!((listen.local_addr.as_str(), listen.local_port), ("127.0.0.1", 631));
+        assert_eq!((listen.state.as_str(), listen.inode), ("LISTEN", 12345));
+
+        let established = parse_inet_line("tcp", TCP_ESTABLISHED).unwrap();
+        assert_eq!((established.local_addr.as_str(), established.local_port), ("10.0.2.15", 50000));
+        assert_eq!((established.remote_addr.as_str(), established.remote_port), ("34.216.184.93", 443));
+        assert_eq!((established.state.as_str(), established.inode), ("ESTABLISHED", 23456));
+
+        // The same state codes mean something else for UDP
+        let udp = TCP_LISTEN.replacen(" 0A ", " 07 ", 1);
+        assert_eq!(parse_inet_line("udp", &udp).unwrap().state, "UNCONN");
+        assert!(parse_inet_line("tcp", "  sl  local_address rem_address   st tx_queue").is_none());
+    }
+
+    #[test]
+    fn tcp_states() {
+        let names: Vec<&str> = (1..=0x0C).map(tcp_state_name).collect();
+        assert_eq!(names, ["ESTABLISHED", "SYN_SENT", "SYN_RECV", "FIN_WAIT1", "FIN_WAIT2", "TIME_WAIT",
+                           "CLOSE", "CLOSE_WAIT", "LAST_ACK", "LISTEN", "CLOSING", "NEW_SYN_RECV"]);
+        assert_eq!(tcp_state_name(0), "UNKNOWN");
+    }
+
+    #[test]
+    fn unix_lines() {
+        let listening = parse_unix_line("0000000000000000: 00000002 00000000 00010000 0001 01 23456 /run/my app.sock").unwrap();
+        assert_eq!((listening.protocol, listening.state.as_str()), ("unix_stream", "LISTEN"));
+        assert_eq!((listening.local_addr.as_str(), listening.inode), ("/run/my app.sock", 23456));
+
+        let unnamed = parse_unix_line("0000000000000000: 00000003 00000000 00000000 0001 03  4567").unwrap();
+        assert_eq!((unnamed.state.as_str(), unnamed.local_addr.as_str(), unnamed.inode), ("CONNECTED", "", 4567));
+
+        let abstract_dgram = parse_unix_line("0000000000000000: 00000002 00000000 00000000 0002 01 345 @/tmp/.X11-unix/X0").unwrap();
+        assert_eq!((abstract_dgram.protocol, abstract_dgram.local_addr.as_str()), ("unix_dgram", "@/tmp/.X11-unix/X0"));
+
+        assert!(parse_unix_line("Num       RefCount Protocol Flags    Type St Inode Path").is_none());
+    }
+
+    #[test]
+    fn socket_links() {
+        assert_eq!(parse_socket_link("socket:[12345]"), Some(12345));
+        assert_eq!(parse_socket_link("pipe:[12345]"), None);
+        assert_eq!(parse_socket_link("/dev/null"), None);
+    }
+}
@@ -0,0 +1,284 @@
+//! Figures read straight from `/proc` that sysinfo does not expose: per-process
+//! details (deleted executables, start ticks, credentials, I/O counters) and
+//! system-wide load averages, pressure stall information and CPU times.
+
+use crate::types::{CpuUsage, IoCounters, LoadAverage, Pressure};
+#[cfg(target_os = "linux")]
+use crate::types::{PressureAverages, PressureStall};
+
+/// Whether the executable backing `pid` was unlinked after the process started
+///
+/// The kernel appends ` (deleted)` to the `/proc/<pid>/exe` link target in that
+/// case; sysinfo strips the marker, so we read the link ourselves.
+#[cfg(target_os = "linux")]
+pub fn exe_deleted(pid: u32) -> bool {
+    std::fs::read_link(format!("/proc/{}/exe", pid))
+        .map(|target| target.to_string_lossy().ends_with(" (deleted)"))
+        .unwrap_or(false)
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn exe_deleted(_pid: u32) -> bool {
+    false
+}
+
+/// Start time of `pid` in clock ticks since boot, field 22 of `/proc/<pid>/stat`
+///
+/// Unlike sysinfo's `start_time` (whole seconds since the epoch) this is precise
+/// enough to tell a recycled PID apart from the process that used it before.
+#[cfg(target_os = "linux")]
+pub fn start_ticks(pid: u32) -> Option<u64> {
+    let stat = std::fs::read_to_string(format!("/proc/{}/stat", pid)).ok()?;
+    // The command name may itself contain spaces or ')', so parse after the last ')'
+    let after_comm = &stat[stat.rfind(')')? + 1..];
+    after_comm.split_whitespace().nth(19)?.parse().ok()
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn start_ticks(_pid: u32) -> Option<u64> {
+    None
+}
+
+/// Real, effective and saved user and group IDs of a process
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Credentials {
+    pub uid: u32,
+    pub euid: u32,
+    pub suid: u32,
+    pub gid: u32,
+    pub egid: u32,
+}
+
+/// The `Uid:` and `Gid:` lines of `/proc/<pid>/status`, each `real effective saved filesystem`
+#[cfg(target_os = "linux")]
+pub fn credentials(pid: u32) -> Option<Credentials> {
+    let status = std::fs::read_to_string(format!("/proc/{}/status", pid)).ok()?;
+    let ids = |prefix: &str| -> Option<Vec<u32>> {
+        let line = status.lines().find_map(|l| l.strip_prefix(prefix))?;
+        line.split_whitespace().map(|id| id.parse().ok()).collect()
+    };
+
+    let uids = ids("Uid:")?;
+    let gids = ids("Gid:")?;
+    Some(Credentials {
+        uid: *uids.first()?,
+        euid: *uids.get(1)?,
+        suid: *uids.get(2)?,
+        gid: *gids.first()?,
+        egid: *gids.get(1)?,
+    })
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn credentials(_pid: u32) -> Option<Credentials> {
+    None
+}
+
+/// Time since boot in clock ticks, the unit of [`start_ticks`]
+///
+/// Taken just before listing processes, it tells which of them started after the listing.
+#[cfg(target_os = "linux")]
+pub fn uptime_ticks() -> Option<u64> {
+    let uptime = std::fs::read_to_string("/proc/uptime").ok()?;
+    let seconds: f64 = uptime.split_whitespace().next()?.parse().ok()?;
+    // SAFETY: sysconf has no preconditions
+    let ticks_per_second = unsafe { libc::sysconf(libc::_SC_CLK_TCK) };
+    (ticks_per_second > 0).then_some((seconds * ticks_per_second as f64) as u64)
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn uptime_ticks() -> Option<u64> {
+    None
+}
+
+/// The counters in `/proc/<pid>/io`, readable only with ptrace access to the process
+#[cfg(target_os = "linux")]
+pub fn io_counters(pid: u32) -> Option<IoCounters> {
+    let io = std::fs::read_to_string(format!("/proc/{}/io", pid)).ok()?;
+    let field = |name: &str| -> Option<u64> {
+        io.lines().find_map(|l| l.strip_prefix(name)?.strip_prefix(':'))?.trim().parse().ok()
+    };
+    Some(IoCounters {
+        rchar: field("rchar")?,
+        wchar: field("wchar")?,
+        syscr: field("syscr")?,
+        syscw: field("syscw")?,
+        read_bytes: field("read_bytes")?,
+        write_bytes: field("write_bytes")?,
+        cancelled_write_bytes: field("cancelled_write_bytes")?,
+    })
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn io_counters(_pid: u32) -> Option<IoCounters> {
+    None
+}
+
+/// Load averages and task counts, `/proc/loadavg`
+#[cfg(target_os = "linux")]
+pub fn load_average() -> Option<LoadAverage> {
+    loadavg(&std::fs::read_to_string("/proc/loadavg").ok()?)
+}
+
+/// `0.52 0.58 0.59 2/1301 424242`: three load averages, runnable/all tasks and the last PID
+#[cfg(target_os = "linux")]
+fn loadavg(text: &str) -> Option<LoadAverage> {
+    let mut fields = text.split_whitespace();
+    let mut average = || fields.next()?.parse().ok();
+    let (one, five, fifteen) = (average()?, average()?, average()?);
+    let (runnable, tasks) = fields.next()?.split_once('/')?;
+    Some(LoadAverage { one, five, fifteen, runnable: runnable.parse().ok()?, tasks: tasks.parse().ok()? })
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn load_average() -> Option<LoadAverage> {
+    None
+}
+
+/// CPU, memory and I/O pressure; `None` when the kernel has no PSI support
+#[cfg(target_os = "linux")]
+pub fn pressure() -> Option<Pressure> {
+    let stall = |resource: &str| {
+        pressure_stall(&std::fs::read_to_string(format!("/proc/pressure/{}", resource)).ok()?)
+    };
+    let pressure = Pressure { cpu: stall("cpu"), memory: stall("memory"), io: stall("io") };
+    (pressure.cpu.is_some() || pressure.memory.is_some() || pressure.io.is_some()).then_some(pressure)
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn pressure() -> Option<Pressure> {
+    None
+}
+
+/// One `/proc/pressure/<resource>` file: a `some` line and, except for CPU on older
+/// kernels, a `full` line
+#[cfg(target_os = "linux")]
+fn pressure_stall(text: &str) -> Option<PressureStall> {
+    let line = |kind: &str| text.lines()
+        .find_map(|l| l.strip_prefix(kind)?.strip_prefix(' '))
+        .and_then(pressure_averages);
+    Some(PressureStall { some: line("some")?, full: line("full") })
+}
+
+/// `avg10=0.28 avg60=1.77 avg300=2.28 total=63758441`
+#[cfg(target_os = "linux")]
+fn pressure_averages(line: &str) -> Option<PressureAverages> {
+    let field = |name: &str| line.split_whitespace()
+        .find_map(|pair| pair.strip_prefix(name)?.strip_prefix('='));
+    Some(PressureAverages {
+        avg10: field("avg10")?.parse().ok()?,
+        avg60: field("avg60")?.parse().ok()?,
+        avg300: field("avg300")?.parse().ok()?,
+        total: field("total")?.parse().ok()?,
+    })
+}
+
+/// Cumulative time in clock ticks spent in each state, one `cpu` line of `/proc/stat`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CpuTimes {
+    // N of `cpuN`; `None` for the line that sums every core
+    pub id: Option<u32>,
+    user: u64,
+    nice: u64,
+    system: u64,
+    idle: u64,
+    iowait: u64,
+    irq: u64,
+    softirq: u64,
+    steal: u64,
+}
+
+impl CpuTimes {
+    /// Share of the time between `earlier` and these counters spent in each state
+    pub fn usage_since(&self, earlier: &CpuTimes) -> CpuUsage {
+        let deltas = [
+            self.user.saturating_sub(earlier.user),
+            self.nice.saturating_sub(earlier.nice),
+            self.system.saturating_sub(earlier.system),
+            self.irq.saturating_sub(earlier.irq),
+            self.softirq.saturating_sub(earlier.softirq),
+            self.iowait.saturating_sub(earlier.iowait),
+            self.steal.saturating_sub(earlier.steal),
+            self.idle.saturating_sub(earlier.idle),
+        ];
+        let total: u64 = deltas.iter().sum();
+        if total == 0 {
+            return CpuUsage { idle: 100.0, ..CpuUsage::default() };
+        }
+        let percent = |ticks: u64| ticks as f64 / total as f64 * 100.0;
+        let [user, nice, system, irq, softirq, iowait, steal, idle] = deltas.map(percent);
+        CpuUsage { user, nice, system, irq, softirq, iowait, steal, idle }
+    }
+}
+
+/// The summed `cpu` line followed by one line per online core
+#[cfg(target_os = "linux")]
+pub fn cpu_times() -> Option<Vec<CpuTimes>> {
+    let stat = std::fs::read_to_string("/proc/stat").ok()?;
+    let times: Vec<CpuTimes> = stat.lines()
+        .filter_map(|line| {
+            let mut fields = line.split_whitespace();
+            let id = match fields.next()?.strip_prefix("cpu")? {
+                "" => None,
+                n => Some(n.parse().ok()?),
+            };
+            // Guest time is already counted in user and nice
+            let values: Vec<u64> = fields.take(8).map(|v| v.parse().ok()).collect::<Option<_>>()?;
+            let &[user, nice, system, idle, iowait, irq, softirq, steal] = values.as_slice() else {
+                return None;
+            };
+            Some(CpuTimes { id, user, nice, system, idle, iowait, irq, softirq, steal })
+        })
+        .collect();
+    (!times.is_empty()).then_some(times)
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn cpu_times() -> Option<Vec<CpuTimes>> {
+    None
+}
+
+#[cfg(all(test, target_os = "linux"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn load_averages_and_task_counts() {
+        let load = loadavg("0.52 0.58 0.59 2/1301 424242\n").expect("loadavg");
+        assert_eq!(load, LoadAverage { one: 0.52, five: 0.58, fifteen: 0.59, runnable: 2, tasks: 1301 });
+        assert_eq!(loadavg("12.00 8.50 4.25 17/65536 1").map(|l| (l.one, l.runnable, l.tasks)), Some((12.0, 17, 65536)));
+    }
+
+    #[test]
+    fn malformed_loadavg_is_rejected() {
+        for text in ["", "0.52 0.58", "0.52 0.58 0.59", "0.52 0.58 0.59 1301", "0.52 x 0.59 2/1301", "0.52 0.58 0.59 2/-1"] {
+            assert_eq!(loadavg(text), None, "{:?}", text);
+        }
+    }
+
+    #[test]
+    fn pressure_with_some_and_full_lines() {
+        let stall = pressure_stall("some avg10=1.50 avg60=0.75 avg300=0.10 total=123456\n\
+                                    full avg10=0.25 avg60=0.00 avg300=0.00 total=789\n").expect("pressure");
+        assert_eq!(stall.some, PressureAverages { avg10: 1.5, avg60: 0.75, avg300: 0.1, total: 123456 });
+        assert_eq!(stall.full, Some(PressureAverages { avg10: 0.25, avg60: 0.0, avg300: 0.0, total: 789 }));
+    }
+
+    #[test]
+    fn cpu_pressure_may_lack_a_full_line() {
+        let stall = pressure_stall("some avg10=0.00 avg60=0.00 avg300=0.00 total=0\n").expect("pressure");
+        assert_eq!(stall.full, None);
+        assert_eq!(stall.some.total, 0);
+    }
+
+    #[test]
+    fn malformed_pressure_is_rejected() {
+        assert_eq!(pressure_stall(""), None);
+        assert_eq!(pressure_stall("full avg10=0.25 avg60=0.00 avg300=0.00 total=789"), None);
+        assert_eq!(pressure_stall("some avg10=0.25 avg60=0.00 total=789"), None);
+        assert_eq!(pressure_stall("someavg10=0.25 avg60=0.00 avg300=0.00 total=789"), None);
+        // A broken full line only loses the full figures
+        let stall = pressure_stall("some avg10=1.00 avg60=0.00 avg300=0.00 total=1\nfull avg10=x").expect("pressure");
+        assert_eq!(stall.full, None);
+    }
+}
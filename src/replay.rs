@@ -0,0 +1,226 @@
+//! Replay of recorded captures through the same display and alert pipeline as
+//! live monitoring.
+
+use std::collections::HashMap;
+use std::io;
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+use crossterm::event::{self, Event, KeyCode, KeyEventKind};
+
+use crate::capture::CaptureIndex;
+use crate::detectors::DetectorEngine;
+use crate::filter::Filter;
+use crate::monitor::process_events;
+use crate::config::Config;
+use crate::types::MonitorSnapshot;
+use crate::ui::{self, Frame, Tui};
+
+const SPEEDS: &[f64] = &[0.25, 0.5, 1.0, 2.0, 4.0, 8.0, 16.0, 32.0];
+
+const HELP: &str = concat!("q: quit | space: play/pause | ←/→: step | +/-: speed | g/G: first/last | ", ui::view_keys!());
+
+/// Delay used when two snapshots carry no usable time difference
+const DEFAULT_FRAME_DELAY: Duration = Duration::from_secs(1);
+
+pub struct Replayer {
+    // Snapshots are decoded as they are shown, so long captures are not held in memory
+    capture: CaptureIndex,
+    position: usize,
+    playing: bool,
+    speed_index: usize,
+    verbose: bool,
+    detectors: DetectorEngine,
+    filter: Option<Filter>,
+}
+
+impl Replayer {
+    pub fn load(path: &Path, speed: f64, verbose: bool, filter: Option<Filter>) -> io::Result<Self> {
+        let capture = CaptureIndex::open(path)?;
+        if capture.is_empty() {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "Capture contains no snapshots"));
+        }
+
+        // Closest supported speed to the one requested
+        let speed_index = SPEEDS.iter()
+            .enumerate()
+            .min_by(|(_, a), (_, b)| (*a - speed).abs().total_cmp(&(*b - speed).abs()))
+            .map_or(2, |(i, _)| i);
+
+        Ok(Self {
+            capture,
+            position: 0,
+            playing: true,
+            speed_index,
+            verbose,
+            detectors: DetectorEngine::with_builtins(),
+            filter,
+        })
+    }
+
+    /// Play back using the display profile and thresholds of `config`
+    pub fn run(&mut self, config: &Config) -> io::Result<()> {
+        let mut tui = Tui::enter(self.verbose, true, config.display.clone(), config.thresholds)?;
+        tui.set_filter(self.filter.as_ref());
+        let mut shown = self.position;
+        let mut frame = self.current_frame()?;
+        tui.record_events(&frame);
+        self.rebuild_history(&mut tui)?;
+        let mut next_frame = Instant::now() + self.frame_delay();
+
+        loop {
+            if shown != self.position {
+                let stepped = self.position == shown + 1;
+                shown = self.position;
+                frame = self.current_frame()?;
+                // The alert panel shows what happened in the frame on screen
+                tui.clear_alerts();
+                tui.record_events(&frame);
+                // Sparklines cover the frames before this one, wherever playback jumped
+                if stepped {
+                    tui.record_history(&self.capture.get(shown)?);
+                } else {
+                    self.rebuild_history(&mut tui)?;
+                }
+            }
+
+            tui.draw(&Frame {
+                snapshot: &frame,
+                status: self.status_line(),
+                help: HELP,
+            })?;
+
+            let wait = if self.playing {
+                next_frame.saturating_duration_since(Instant::now())
+            } else {
+                Duration::from_secs(3600)
+            };
+            if !event::poll(wait)? {
+                if self.playing {
+                    self.step_forward();
+                    next_frame = Instant::now() + self.frame_delay();
+                }
+                continue;
+            }
+
+            let Event::Key(key) = event::read()? else {
+                continue;
+            };
+            if key.kind != KeyEventKind::Press {
+                continue;
+            }
+            if tui.is_editing() {
+                tui.handle_key(key, &frame);
+                if let Some(filter) = tui.take_filter_change() {
+                    self.filter = filter;
+                    frame = self.current_frame()?;
+                    tui.clear_alerts();
+                    tui.record_events(&frame);
+                }
+                continue;
+            }
+            if ui::is_quit(&key) {
+                break;
+            }
+
+            match key.code {
+                KeyCode::Char(' ') | KeyCode::Char('p') => {
+                    self.playing = !self.playing;
+                    next_frame = Instant::now() + self.frame_delay();
+                }
+                KeyCode::Right | KeyCode::Char('.') => {
+                    self.playing = false;
+                    self.step_forward();
+                }
+                KeyCode::Left | KeyCode::Char(',') => {
+                    self.playing = false;
+                    self.position = self.position.saturating_sub(1);
+                }
+                KeyCode::Char('+') | KeyCode::Char('=') => {
+                    self.speed_index = (self.speed_index + 1).min(SPEEDS.len() - 1);
+                    next_frame = Instant::now() + self.frame_delay();
+                }
+                KeyCode::Char('-') => {
+                    self.speed_index = self.speed_index.saturating_sub(1);
+                    next_frame = Instant::now() + self.frame_delay();
+                }
+                KeyCode::Char('g') => self.position = 0,
+                KeyCode::Char('G') => self.position = self.capture.len() - 1,
+                _ => {
+                    tui.handle_key(key, &frame);
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn step_forward(&mut self) {
+        if self.position + 1 < self.capture.len() {
+            self.position += 1;
+        } else {
+            // Stay on the last frame rather than looping
+            self.playing = false;
+        }
+    }
+
+    /// Recorded time between the current and next snapshot, scaled by the replay speed
+    fn frame_delay(&self) -> Duration {
+        if self.position + 1 >= self.capture.len() {
+            return DEFAULT_FRAME_DELAY;
+        }
+        let recorded = (self.capture.timestamp(self.position + 1) - self.capture.timestamp(self.position))
+            .to_std()
+            .unwrap_or(DEFAULT_FRAME_DELAY);
+
+        recorded.div_f64(SPEEDS[self.speed_index])
+            .clamp(Duration::from_millis(50), Duration::from_secs(10))
+    }
+
+    /// The snapshot at the current position, with events and findings filled in
+    /// for captures recorded before they were stored
+    fn current_frame(&mut self) -> io::Result<MonitorSnapshot> {
+        // The previous snapshot first, so the capture is read forward
+        let previous = match self.position.checked_sub(1) {
+            Some(i) => Some(self.capture.get(i)?),
+            None => None,
+        };
+        let previous = previous.as_ref();
+        let mut snapshot = self.capture.get(self.position)?;
+
+        if let Some(previous) = previous
+            && snapshot.events.is_empty() {
+            let previous_processes: HashMap<_, _> = previous.processes.iter()
+                .map(|p| (p.key(), p.clone()))
+                .collect();
+            snapshot.events = process_events(&previous_processes, &snapshot);
+        }
+
+        if snapshot.findings.is_empty() {
+            snapshot.findings = self.detectors.evaluate(&snapshot, previous);
+        }
+
+        if let Some(filter) = &self.filter {
+            filter.apply(&mut snapshot);
+        }
+
+        Ok(snapshot)
+    }
+
+    /// Fill the sparklines from the snapshots up to the current one
+    fn rebuild_history(&mut self, tui: &mut Tui) -> io::Result<()> {
+        tui.clear_history();
+        for i in (self.position + 1).saturating_sub(ui::HISTORY_SAMPLES)..=self.position {
+            tui.record_history(&self.capture.get(i)?);
+        }
+        Ok(())
+    }
+
+    fn status_line(&self) -> String {
+        format!("⏪ Replay {}/{} | {} {}x",
+                self.position + 1,
+                self.capture.len(),
+                if self.playing { "▶" } else { "⏸" },
+                SPEEDS[self.speed_index])
+    }
+}
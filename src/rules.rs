@@ -0,0 +1,327 @@
+//! Hide and rename rules with explicit match modes.
+//!
+//! A rule matches one field of a process (name, executable path, command line or
+//! user) in one of several modes and either hides the process or shows it under
+//! another name. Every mode is compiled to a regular expression once, when the
+//! rule is created or loaded.
+
+use std::fmt;
+
+use clap::ValueEnum;
+use regex::{Regex, RegexBuilder};
+use serde::{Deserialize, Serialize};
+
+use crate::types::ProcessInfo;
+
+/// The part of a process a rule looks at
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MatchField {
+    // Original process name, before any rename
+    #[default]
+    Name,
+    Exe,
+    // Arguments joined with spaces
+    Cmd,
+    // User name or numeric UID
+    User,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MatchMode {
+    #[default]
+    Exact,
+    Prefix,
+    Contains,
+    // `*` matches any run of characters (including `/`), `?` one character, `[...]` a class
+    Glob,
+    Regex,
+}
+
+/// A hide rule, or a rename rule when `rename` is set
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(try_from = "RuleSpec", into = "RuleSpec")]
+pub struct Rule {
+    pub field: MatchField,
+    pub mode: MatchMode,
+    pub pattern: String,
+    pub ignore_case: bool,
+    // Rules with a higher priority are tried first; equal priorities keep their order
+    pub priority: i32,
+    pub rename: Option<String>,
+    regex: Regex,
+}
+
+/// How a rule is written in the configuration file
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RuleSpec {
+    #[serde(default)]
+    field: MatchField,
+    #[serde(default, rename = "match")]
+    mode: MatchMode,
+    pattern: String,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    ignore_case: bool,
+    #[serde(default, skip_serializing_if = "is_zero")]
+    priority: i32,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    rename: Option<String>,
+}
+
+fn is_zero(n: &i32) -> bool {
+    *n == 0
+}
+
+impl Rule {
+    pub fn new(field: MatchField, mode: MatchMode, pattern: &str, ignore_case: bool, priority: i32,
+               rename: Option<String>) -> Result<Self, String> {
+        let source = match mode {
+            MatchMode::Exact => format!("^{}$", regex::escape(pattern)),
+            MatchMode::Prefix => format!("^{}", regex::escape(pattern)),
+            MatchMode::Contains => regex::escape(pattern),
+            MatchMode::Glob => glob_to_regex(pattern),
+            MatchMode::Regex => pattern.to_string(),
+        };
+        let regex = RegexBuilder::new(&source)
+            .case_insensitive(ignore_case)
+            .build()
+            .map_err(|e| {
+                // The regex crate's message spans several lines; the last one has the reason
+                let reason = e.to_string();
+                let reason = reason.lines().last().unwrap_or_default().trim_start_matches("error: ").to_string();
+                format!("invalid {} pattern '{}': {}", mode_name(mode), pattern, reason)
+            })?;
+
+        Ok(Self { field, mode, pattern: pattern.to_string(), ignore_case, priority, rename, regex })
+    }
+
+    /// A hide rule for names containing `name`, ignoring case, as `config hide-name` adds
+    pub fn hidden_name(name: &str) -> Self {
+        Self::new(MatchField::Name, MatchMode::Contains, name, true, 0, None)
+            .expect("an escaped pattern is a valid regex")
+    }
+
+    pub fn is_hide(&self) -> bool {
+        self.rename.is_none()
+    }
+
+    pub fn matches(&self, process: &ProcessInfo) -> bool {
+        match self.field {
+            MatchField::Name => self.regex.is_match(&process.name),
+            MatchField::Exe => process.exe_path.as_deref().is_some_and(|exe| self.regex.is_match(exe)),
+            MatchField::Cmd => !process.cmd.is_empty() && self.regex.is_match(&process.cmd.join(" ")),
+            MatchField::User => {
+                process.user_name.as_deref().is_some_and(|name| self.regex.is_match(name))
+                    || process.user_id.is_some_and(|uid| self.regex.is_match(&uid.to_string()))
+            }
+        }
+    }
+}
+
+fn mode_name(mode: MatchMode) -> String {
+    mode.to_possible_value().expect("no skipped variants").get_name().to_string()
+}
+
+/// Translate a shell-style glob into an anchored regular expression
+fn glob_to_regex(glob: &str) -> String {
+    let chars: Vec<char> = glob.chars().collect();
+    let mut source = String::from("^");
+    let mut i = 0;
+    while i < chars.len() {
+        match chars[i] {
+            '*' => source.push_str(".*"),
+            '?' => source.push('.'),
+            '[' if let Some((class, end)) = glob_class(&chars, i + 1) => {
+                source.push_str(&class);
+                i = end;
+            }
+            // Including a `[` that never closes, which the shell takes literally
+            c => source.push_str(&escape_char(c)),
+        }
+        i += 1;
+    }
+    source.push('$');
+    source
+}
+
+/// The regex class for the glob class starting at `start`, just after its `[`,
+/// and the position of the closing `]`
+///
+/// Only ranges and a leading `!` or `^` are special; everything else, such as the
+/// `&&`, `--` and `~~` the regex crate reads as set operators, is escaped.
+fn glob_class(chars: &[char], start: usize) -> Option<(String, usize)> {
+    let mut class = String::from("[");
+    let mut i = start;
+    if matches!(chars.get(i), Some('!' | '^')) {
+        class.push('^');
+        i += 1;
+    }
+    let first = i;
+
+    loop {
+        let c = *chars.get(i)?;
+        // A `]` right after the opening is part of the class
+        if c == ']' && i > first {
+            class.push(']');
+            return Some((class, i));
+        }
+        class.push_str(&escape_char(c));
+        if chars.get(i + 1) == Some(&'-') && chars.get(i + 2).is_some_and(|&end| end != ']') {
+            class.push('-');
+            class.push_str(&escape_char(chars[i + 2]));
+            i += 2;
+        }
+        i += 1;
+    }
+}
+
+fn escape_char(c: char) -> String {
+    regex::escape(c.encode_utf8(&mut [0; 4]))
+}
+
+impl fmt::Display for Rule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let field = self.field.to_possible_value().expect("no skipped variants");
+        write!(f, "{} {} \"{}\"", field.get_name(), mode_name(self.mode), self.pattern)?;
+        if self.ignore_case {
+            f.write_str(" (ignore case)")?;
+        }
+        if self.priority != 0 {
+            write!(f, " [priority {}]", self.priority)?;
+        }
+        match &self.rename {
+            Some(name) => write!(f, " -> {}", name),
+            None => f.write_str(" -> hide"),
+        }
+    }
+}
+
+impl TryFrom<RuleSpec> for Rule {
+    type Error = String;
+
+    fn try_from(spec: RuleSpec) -> Result<Self, Self::Error> {
+        Rule::new(spec.field, spec.mode, &spec.pattern, spec.ignore_case, spec.priority, spec.rename)
+    }
+}
+
+impl From<Rule> for RuleSpec {
+    fn from(rule: Rule) -> Self {
+        Self {
+            field: rule.field,
+            mode: rule.mode,
+            pattern: rule.pattern,
+            ignore_case: rule.ignore_case,
+            priority: rule.priority,
+            rename: rule.rename,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::fixtures::process;
+
+    fn rule(field: MatchField, mode: MatchMode, pattern: &str) -> Rule {
+        Rule::new(field, mode, pattern, false, 0, None).expect("valid rule")
+    }
+
+    fn name_matches(mode: MatchMode, pattern: &str, name: &str) -> bool {
+        rule(MatchField::Name, mode, pattern).matches(&process(300, json!({"name": name})))
+    }
+
+    #[test]
+    fn modes_match_the_whole_name_its_start_or_any_part() {
+        assert!(name_matches(MatchMode::Exact, "sshd", "sshd"));
+        assert!(!name_matches(MatchMode::Exact, "sshd", "sshd-session"));
+        assert!(name_matches(MatchMode::Prefix, "ssh", "sshd"));
+        assert!(!name_matches(MatchMode::Prefix, "shd", "sshd"));
+        assert!(name_matches(MatchMode::Contains, "sh", "bash"));
+        assert!(!name_matches(MatchMode::Contains, "zsh", "bash"));
+        // Only regex mode reads metacharacters
+        assert!(name_matches(MatchMode::Exact, "a.b", "a.b"));
+        assert!(!name_matches(MatchMode::Exact, "a.b", "axb"));
+        assert!(name_matches(MatchMode::Regex, "^kworker/\\d+:", "kworker/0:1"));
+        assert!(!name_matches(MatchMode::Regex, "^kworker/\\d+:", "kworker/u8"));
+    }
+
+    #[test]
+    fn globs_match_the_whole_name() {
+        assert!(name_matches(MatchMode::Glob, "python3*", "python3.12"));
+        assert!(!name_matches(MatchMode::Glob, "python3*", "ipython3"));
+        assert!(name_matches(MatchMode::Glob, "php-fpm?", "php-fpm8"));
+        assert!(!name_matches(MatchMode::Glob, "php-fpm?", "php-fpm8.2"));
+        assert!(name_matches(MatchMode::Glob, "tty[0-9]", "tty3"));
+        assert!(!name_matches(MatchMode::Glob, "tty[!0-9]", "tty3"));
+        assert!(name_matches(MatchMode::Glob, "a+b(c)", "a+b(c)"));
+    }
+
+    #[test]
+    fn ignore_case_applies_to_every_mode() {
+        let rule = Rule::new(MatchField::Name, MatchMode::Glob, "NGINX*", true, 0, None).expect("valid rule");
+        assert!(rule.matches(&process(300, json!({"name": "nginx: worker"}))));
+        assert!(Rule::hidden_name("SSH").matches(&process(300, json!({"name": "sshd"}))));
+    }
+
+    #[test]
+    fn fields_other_than_the_name() {
+        let agent = process(300, json!({
+            "exe_path": "/opt/agent/bin/run",
+            "cmd": ["/opt/agent/bin/run", "--token", "x"],
+            "user_id": 998,
+            "user_name": "agent",
+        }));
+        assert!(rule(MatchField::Exe, MatchMode::Prefix, "/opt/agent/").matches(&agent));
+        assert!(rule(MatchField::Cmd, MatchMode::Contains, "run --token").matches(&agent));
+        assert!(rule(MatchField::User, MatchMode::Exact, "agent").matches(&agent));
+        assert!(rule(MatchField::User, MatchMode::Exact, "998").matches(&agent));
+
+        let kernel = process(2, json!({"exe_path": null, "cmd": [], "user_id": null}));
+        assert!(!rule(MatchField::Exe, MatchMode::Glob, "*").matches(&kernel));
+        assert!(!rule(MatchField::Cmd, MatchMode::Regex, "").matches(&kernel));
+        assert!(!rule(MatchField::User, MatchMode::Regex, "").matches(&kernel));
+    }
+
+    #[test]
+    fn glob_wildcards_and_literals() {
+        assert_eq!(glob_to_regex("*.sh"), "^.*\\.sh$");
+        assert_eq!(glob_to_regex("a?c"), "^a.c$");
+        assert_eq!(glob_to_regex("a[b"), "^a\\[b$");
+        assert_eq!(glob_to_regex("[]"), "^\\[\\]$");
+    }
+
+    #[test]
+    fn glob_classes_escape_their_contents() {
+        assert_eq!(glob_to_regex("[abc]"), "^[abc]$");
+        assert_eq!(glob_to_regex("[!a-c]"), "^[^a-c]$");
+        assert_eq!(glob_to_regex("[^a]"), "^[^a]$");
+        // A leading `]` and leading or trailing `-` are literal
+        assert_eq!(glob_to_regex("[]a]"), "^[\\]a]$");
+        assert_eq!(glob_to_regex("[!]]"), "^[^\\]]$");
+        assert_eq!(glob_to_regex("[-a-]"), "^[\\-a\\-]$");
+        // Set operators and other class syntax of the regex crate
+        assert_eq!(glob_to_regex("[a&&b]"), "^[a\\&\\&b]$");
+        assert_eq!(glob_to_regex("[--/]"), "^[\\--/]$");
+        assert_eq!(glob_to_regex("[~~[\\]"), "^[\\~\\~\\[\\\\]$");
+    }
+
+    #[test]
+    fn glob_classes_compile_and_match_literally() {
+        for (glob, name) in [("[]a]", "]"), ("[a&&b]", "&"), ("[--/]", "."), ("[~~]", "~"), ("[[:]", ":"), ("[\\]", "\\")] {
+            assert!(name_matches(MatchMode::Glob, glob, name), "{} should match {}", glob, name);
+        }
+        assert!(!name_matches(MatchMode::Glob, "[a&&b]", "c"));
+    }
+
+    #[test]
+    fn invalid_patterns_are_reported_on_one_line() {
+        let error = Rule::new(MatchField::Name, MatchMode::Regex, "(", false, 0, None).unwrap_err();
+        assert!(error.starts_with("invalid regex pattern '(': "), "{}", error);
+        assert!(!error.contains('\n'));
+        assert!(Rule::new(MatchField::Name, MatchMode::Glob, "[z-a]", false, 0, None).is_err());
+    }
+}
@@ -1,76 +1,49 @@
 use std::collections::HashMap;
-use serde::{Deserialize, Serialize};
-use std::fs;
 use std::path::Path;
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct StealthConfig {
-    // Processes to completely hide by name
-    pub hidden_processes: Vec<String>,
-    // Process name mappings (original_name -> display_name)
-    pub renamed_processes: HashMap<String, String>,
-    // Processes to hide by PID
-    pub hidden_pids: Vec<u32>,
-}
+use clap::ValueEnum;
 
-impl Default for StealthConfig {
-    fn default() -> Self {
-        Self {
-            hidden_processes: Vec::new(),
-            renamed_processes: HashMap::new(),
-            hidden_pids: Vec::new(),
-        }
-    }
-}
+use crate::columns::{Column, ColumnSpec, SortKey, SortOrder};
+use crate::config::{Config, ConfigStore};
+use crate::rules::Rule;
+use crate::types::ProcessInfo;
 
 pub struct StealthManager {
-    config: StealthConfig,
-    config_path: String,
+    config: Config,
+    store: ConfigStore,
 }
 
 impl StealthManager {
-    pub fn new() -> Self {
-        let config_path = "stealth_config.json".to_string();
-        let config = Self::load_config(&config_path).unwrap_or_default();
-
-        Self {
-            config,
-            config_path,
-        }
+    /// Load the layered configuration, with `config_path` in place of the user's default file
+    pub fn load(config_path: Option<&Path>) -> Result<Self, Box<dyn std::error::Error>> {
+        let mut store = ConfigStore::locate(config_path)?;
+        let mut config = store.load()?;
+        sort_rules(&mut config.rules);
+        Ok(Self { config, store })
     }
 
-    /// Load stealth configuration from file
-    fn load_config(path: &str) -> Result<StealthConfig, Box<dyn std::error::Error>> {
-        if !Path::new(path).exists() {
-            // Create default config file
-            let default_config = StealthConfig::default();
-            let json = serde_json::to_string_pretty(&default_config)?;
-            fs::write(path, json)?;
-            return Ok(default_config);
-        }
-
-        let content = fs::read_to_string(path)?;
-        let config: StealthConfig = serde_json::from_str(&content)?;
-        Ok(config)
+    /// Save current configuration to file
+    pub fn save_config(&mut self) -> Result<(), Box<dyn std::error::Error>> {
+        self.store.save(&self.config)
     }
 
-    /// Save current configuration to file
-    pub fn save_config(&self) -> Result<(), Box<dyn std::error::Error>> {
-        let json = serde_json::to_string_pretty(&self.config)?;
-        fs::write(&self.config_path, json)?;
-        Ok(())
+    /// File the configuration is saved to
+    pub fn config_path(&self) -> Option<&Path> {
+        self.store.path()
     }
 
-    /// Add a process name to the hidden list
+    /// Hide processes whose name contains `process_name`, ignoring case
     pub fn hide_process(&mut self, process_name: &str) {
-        if !self.config.hidden_processes.contains(&process_name.to_string()) {
-            self.config.hidden_processes.push(process_name.to_string());
+        let rule = Rule::hidden_name(process_name);
+        if !self.config.rules.iter().any(|r| r.to_string() == rule.to_string()) {
+            self.add_rule(rule);
         }
     }
 
-    /// Remove a process name from the hidden list
+    /// Remove the rule `hide_process` added for `process_name`
     pub fn unhide_process(&mut self, process_name: &str) {
-        self.config.hidden_processes.retain(|p| p != process_name);
+        let rule = Rule::hidden_name(process_name).to_string();
+        self.config.rules.retain(|r| r.to_string() != rule);
     }
 
     /// Add a PID to the hidden list
@@ -98,10 +71,36 @@ impl StealthManager {
         self.config.renamed_processes.remove(original_name);
     }
 
-    /// Check if a process should be hidden by name
-    pub fn is_process_hidden(&self, process_name: &str) -> bool {
-        self.config.hidden_processes.iter()
-            .any(|hidden| process_name.to_lowercase().contains(&hidden.to_lowercase()))
+    /// Add a hide or rename rule after the rules of the same or higher priority
+    pub fn add_rule(&mut self, rule: Rule) {
+        let position = self.config.rules.iter()
+            .position(|r| r.priority < rule.priority)
+            .unwrap_or(self.config.rules.len());
+        self.config.rules.insert(position, rule);
+    }
+
+    /// Remove the rule at `index` (as listed, starting from 1)
+    pub fn remove_rule(&mut self, index: usize) -> Option<Rule> {
+        (1..=self.config.rules.len()).contains(&index).then(|| self.config.rules.remove(index - 1))
+    }
+
+    /// Get the rules in the order they are tried
+    pub fn get_rules(&self) -> &[Rule] {
+        &self.config.rules
+    }
+
+    /// Why a process is hidden: its PID, or a hide rule that is the first rule matching it
+    ///
+    /// A rename rule tried earlier keeps the process visible. The reason names the
+    /// entry as `config list` shows it, so audits can point at it.
+    pub fn hidden_by(&self, process: &ProcessInfo) -> Option<String> {
+        if self.is_pid_hidden(process.pid) {
+            return Some(format!("hidden pid {}", process.pid));
+        }
+        self.config.rules.iter()
+            .position(|r| r.matches(process))
+            .filter(|&i| self.config.rules[i].is_hide())
+            .map(|i| format!("rule {}: {}", i + 1, self.config.rules[i]))
     }
 
     /// Check if a process should be hidden by PID
@@ -110,25 +109,24 @@ impl StealthManager {
     }
 
     /// Get the display name for a process (renamed if configured)
-    pub fn get_display_name(&self, original_name: &str) -> String {
-        // Check for exact match first
-        if let Some(renamed) = self.config.renamed_processes.get(original_name) {
-            return renamed.clone();
+    ///
+    /// The first matching rename rule wins, then an exact rename mapping, then the
+    /// longest mapping contained in the name (alphabetically first on a tie).
+    pub fn get_display_name(&self, process: &ProcessInfo) -> String {
+        if let Some(rule) = self.config.rules.iter().find(|r| !r.is_hide() && r.matches(process)) {
+            return rule.rename.clone().unwrap_or_default();
         }
 
-        // Check for partial matches
-        for (original, renamed) in &self.config.renamed_processes {
-            if original_name.to_lowercase().contains(&original.to_lowercase()) {
-                return renamed.clone();
-            }
+        let original_name = &process.name;
+        if let Some(renamed) = self.config.renamed_processes.get(original_name) {
+            return renamed.clone();
         }
 
-        original_name.to_string()
-    }
-
-    /// Get list of hidden processes
-    pub fn get_hidden_processes(&self) -> &Vec<String> {
-        &self.config.hidden_processes
+        let lowercase = original_name.to_lowercase();
+        self.config.renamed_processes.iter()
+            .filter(|(original, _)| lowercase.contains(&original.to_lowercase()))
+            .max_by(|(a, _), (b, _)| a.len().cmp(&b.len()).then_with(|| b.cmp(a)))
+            .map_or_else(|| original_name.clone(), |(_, renamed)| renamed.clone())
     }
 
     /// Get list of hidden PIDs
@@ -141,11 +139,50 @@ impl StealthManager {
         &self.config.renamed_processes
     }
 
+    /// Set the default process list columns
+    pub fn set_columns(&mut self, columns: Vec<ColumnSpec>) {
+        self.config.display.columns = columns;
+    }
+
+    /// Set the default sort order of the process list
+    pub fn set_sort(&mut self, sort: SortOrder) {
+        self.config.display.sort = sort;
+    }
+
+    /// Get the whole configuration
+    pub fn config(&self) -> &Config {
+        &self.config
+    }
+
+    /// Replace the configuration, or with `merge` add the rules, hidden PIDs and
+    /// renames of `config` to the current ones; other settings are always replaced
+    pub fn import(&mut self, mut config: Config, merge: bool) {
+        config.convert_hidden_names();
+        if !merge {
+            self.config = config;
+            sort_rules(&mut self.config.rules);
+            return;
+        }
+        for pid in config.hidden_pids {
+            self.hide_pid(pid);
+        }
+        self.config.renamed_processes.extend(config.renamed_processes);
+        for rule in config.rules {
+            if !self.config.rules.iter().any(|r| r.to_string() == rule.to_string()) {
+                self.add_rule(rule);
+            }
+        }
+        self.config.display = config.display;
+        self.config.monitor = config.monitor;
+        self.config.output = config.output;
+        self.config.thresholds = config.thresholds;
+    }
+
     /// Clear all stealth configurations
     pub fn clear_all(&mut self) {
-        self.config.hidden_processes.clear();
         self.config.hidden_pids.clear();
         self.config.renamed_processes.clear();
+        self.config.rules.clear();
     }
 
     /// Interactive configuration menu
@@ -160,10 +197,12 @@ impl StealthManager {
             println!("4. Remove hidden process");
             println!("5. Remove hidden PID");
             println!("6. Remove process rename");
-            println!("7. Show current config");
-            println!("8. Clear all config");
-            println!("9. Save and exit");
-            print!("Select option (1-9): ");
+            println!("7. Set display columns");
+            println!("8. Set sort order");
+            println!("9. Show current config");
+            println!("10. Clear all config");
+            println!("11. Save and exit");
+            print!("Select option (1-11): ");
             io::stdout().flush()?;
 
             let mut input = String::new();
@@ -234,15 +273,55 @@ impl StealthManager {
                     println!("✅ Rename mapping for '{}' removed", name.trim());
                 }
                 "7" => {
-                    self.display_current_config();
+                    let known: Vec<&str> = Column::ALL.iter().map(|c| c.id()).collect();
+                    println!("Available columns: {}", known.join(", "));
+                    print!("Enter columns in order, e.g. pid,name:30,cpu (empty for defaults): ");
+                    io::stdout().flush()?;
+                    let mut list = String::new();
+                    io::stdin().read_line(&mut list)?;
+                    let list = list.trim();
+                    if list.is_empty() {
+                        self.set_columns(ColumnSpec::defaults());
+                        println!("✅ Default columns restored");
+                    } else {
+                        match list.split(',').map(str::parse).collect::<Result<Vec<ColumnSpec>, _>>() {
+                            Ok(columns) => {
+                                self.set_columns(columns);
+                                println!("✅ Columns set to {}", list);
+                            }
+                            Err(e) => println!("❌ {}", e),
+                        }
+                    }
                 }
                 "8" => {
+                    let known: Vec<String> = SortKey::ALL.iter().map(|k| k.to_string()).collect();
+                    print!("Enter sort key ({}), append ' reverse' to flip: ", known.join(", "));
+                    io::stdout().flush()?;
+                    let mut order = String::new();
+                    io::stdin().read_line(&mut order)?;
+                    let mut words = order.split_whitespace();
+                    let key = words.next().map(|k| SortKey::from_str(k, true));
+                    let reverse = words.next().is_some_and(|w| w == "reverse" || w == "rev");
+                    match key {
+                        Some(Ok(key)) => {
+                            self.set_sort(SortOrder { key, reverse });
+                            println!("✅ Sorting by {}{}", key, if reverse { " (reversed)" } else { "" });
+                        }
+                        _ => println!("❌ Invalid sort key"),
+                    }
+                }
+                "9" => {
+                    self.display_current_config();
+                }
+                "10" => {
                     self.clear_all();
                     println!("✅ All stealth configurations cleared");
                 }
-                "9" => {
+                "11" => {
                     self.save_config()?;
-                    println!("✅ Configuration saved!");
+                    if let Some(path) = self.config_path() {
+                        println!("✅ Configuration saved to {}", path.display());
+                    }
                     break;
                 }
                 _ => {
@@ -255,34 +334,57 @@ impl StealthManager {
     }
 
     /// Display current stealth configuration
-    fn display_current_config(&self) {
+    pub fn display_current_config(&self) {
         println!("\n📋 Current Stealth Configuration:");
 
-        if !self.config.hidden_processes.is_empty() {
-            println!("🙈 Hidden Processes:");
-            for process in &self.config.hidden_processes {
-                println!("  - {}", process);
-            }
-        }
-
         if !self.config.hidden_pids.is_empty() {
-            println!("🔢 Hidden PIDs:");
+            println!("🔢 Hidden PIDs{}:", self.source("hidden_pids"));
             for pid in &self.config.hidden_pids {
                 println!("  - {}", pid);
             }
         }
 
         if !self.config.renamed_processes.is_empty() {
-            println!("🎭 Process Renames:");
+            println!("🎭 Process Renames{}:", self.source("renamed_processes"));
             for (original, display) in &self.config.renamed_processes {
                 println!("  - {} -> {}", original, display);
             }
         }
 
-        if self.config.hidden_processes.is_empty()
-            && self.config.hidden_pids.is_empty()
-            && self.config.renamed_processes.is_empty() {
+        if !self.config.rules.is_empty() {
+            // Hidden names of a layer without rules were turned into rules
+            let key = if self.store.source("rules").is_some() { "rules" } else { "hidden_processes" };
+            println!("📏 Rules (in priority order){}:", self.source(key));
+            for (i, rule) in self.config.rules.iter().enumerate() {
+                println!("  {}. {}", i + 1, rule);
+            }
+        }
+
+        if self.config.hidden_pids.is_empty()
+            && self.config.renamed_processes.is_empty()
+            && self.config.rules.is_empty() {
             println!("  No stealth configurations active");
         }
+
+        let display = &self.config.display;
+        let columns: Vec<String> = display.columns.iter().map(|c| String::from(*c)).collect();
+        println!("🖥️  Display:");
+        println!("  - Columns: {}{}", columns.join(","), self.source("display.columns"));
+        println!("  - Sort: {}{}{}", display.sort.key, if display.sort.reverse { " (reversed)" } else { "" },
+                 self.source("display.sort"));
+        if let Some(filter) = &self.config.monitor.filter {
+            println!("🔍 Monitor:");
+            println!("  - Filter: {}{}", filter, self.source("monitor.filter"));
+        }
+    }
+
+    /// " (from FILE)" naming the layer that sets `key`, or "" for built-in defaults
+    fn source(&self, key: &str) -> String {
+        self.store.source(key).map_or_else(String::new, |path| format!(" (from {})", path.display()))
     }
-}
\ No newline at end of file
+}
+
+/// Highest priority first; a stable sort keeps the written order among equal priorities
+fn sort_rules(rules: &mut [Rule]) {
+    rules.sort_by_key(|r| std::cmp::Reverse(r.priority));
+}
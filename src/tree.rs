@@ -0,0 +1,113 @@
+//! Parent/child tree of the processes in a snapshot, like `pstree`.
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+use crate::detectors::unusual_lineage;
+use crate::types::ProcessInfo;
+
+pub struct ProcessTree<'a> {
+    by_pid: HashMap<u32, &'a ProcessInfo>,
+    children: BTreeMap<u32, Vec<u32>>,
+    roots: Vec<u32>,
+}
+
+/// One visible line of the tree
+pub struct TreeRow<'a> {
+    pub process: &'a ProcessInfo,
+    // Box-drawing prefix drawn before the name, e.g. "│  ├─ "
+    pub prefix: String,
+    pub has_children: bool,
+    pub collapsed: bool,
+    // Set when the process is an unusual child of its parent
+    pub unusual: Option<String>,
+}
+
+impl<'a> ProcessTree<'a> {
+    /// Build the tree, leaving out threads; processes whose parent is not in the
+    /// snapshot (init, kthreadd, hidden or filtered parents) become roots
+    pub fn build(processes: &'a [ProcessInfo]) -> Self {
+        let by_pid: HashMap<u32, &ProcessInfo> = processes.iter()
+            .filter(|p| !p.is_thread)
+            .map(|p| (p.pid, p))
+            .collect();
+
+        let mut children: BTreeMap<u32, Vec<u32>> = BTreeMap::new();
+        let mut roots = Vec::new();
+        for (&pid, process) in &by_pid {
+            match process.parent_pid.filter(|ppid| *ppid != pid && by_pid.contains_key(ppid)) {
+                Some(ppid) => children.entry(ppid).or_default().push(pid),
+                None => roots.push(pid),
+            }
+        }
+
+        roots.sort_unstable();
+        for siblings in children.values_mut() {
+            siblings.sort_unstable();
+        }
+
+        Self { by_pid, children, roots }
+    }
+
+    /// Flatten the tree in display order, skipping descendants of collapsed PIDs
+    pub fn rows(&self, collapsed: &HashSet<u32>) -> Vec<TreeRow<'a>> {
+        let mut rows = Vec::new();
+        let count = self.roots.len();
+        for (i, &root) in self.roots.iter().enumerate() {
+            self.push_rows(root, "", i + 1 == count, true, collapsed, &mut rows);
+        }
+        rows
+    }
+
+    fn push_rows(&self, pid: u32, indent: &str, last: bool, root: bool,
+                 collapsed: &HashSet<u32>, rows: &mut Vec<TreeRow<'a>>) {
+        let process = self.by_pid[&pid];
+        let children = self.children.get(&pid).map_or(&[][..], |c| c.as_slice());
+        let is_collapsed = collapsed.contains(&pid) && !children.is_empty();
+
+        let (prefix, child_indent) = if root {
+            (String::new(), String::new())
+        } else if last {
+            (format!("{}└─ ", indent), format!("{}   ", indent))
+        } else {
+            (format!("{}├─ ", indent), format!("{}│  ", indent))
+        };
+
+        let unusual = process.parent_pid
+            .and_then(|ppid| self.by_pid.get(&ppid))
+            .and_then(|parent| unusual_lineage(parent, process));
+
+        rows.push(TreeRow {
+            process,
+            prefix,
+            has_children: !children.is_empty(),
+            collapsed: is_collapsed,
+            unusual,
+        });
+
+        if is_collapsed {
+            return;
+        }
+
+        for (i, &child) in children.iter().enumerate() {
+            self.push_rows(child, &child_indent, i + 1 == children.len(), false, collapsed, rows);
+        }
+    }
+
+    /// The chain of processes from the root down to `pid`
+    pub fn ancestry(&self, pid: u32) -> Vec<&'a ProcessInfo> {
+        let mut chain = Vec::new();
+        let mut current = self.by_pid.get(&pid).copied();
+
+        while let Some(process) = current {
+            // Guard against parent loops in inconsistent snapshots
+            if chain.iter().any(|p: &&ProcessInfo| p.pid == process.pid) {
+                break;
+            }
+            chain.push(process);
+            current = process.parent_pid.and_then(|ppid| self.by_pid.get(&ppid).copied());
+        }
+
+        chain.reverse();
+        chain
+    }
+}
@@ -1,18 +1,129 @@
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Deserializer, Serialize};
 use chrono::{DateTime, Local};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ProcessInfo {
     pub pid: u32,
     pub name: String,
+    // Name reported by the kernel, before any rename; never exported so renames hold in captures
+    #[serde(skip)]
+    pub comm: String,
     pub cmd: Vec<String>,
     pub cpu_usage: f32,
     pub memory: u64,
     pub parent_pid: Option<u32>,
     pub start_time: u64,
+    // Boot-relative start in clock ticks; 0 when unavailable (non-Linux, older captures)
+    #[serde(default)]
+    pub start_ticks: u64,
+    // Real user ID
     pub user_id: Option<u32>,
+    #[serde(default)]
+    pub effective_user_id: Option<u32>,
+    #[serde(default)]
+    pub saved_user_id: Option<u32>,
+    #[serde(default)]
+    pub group_id: Option<u32>,
+    #[serde(default)]
+    pub effective_group_id: Option<u32>,
+    // Names of the real user and group, resolved when the snapshot was taken
+    #[serde(default)]
+    pub user_name: Option<String>,
+    #[serde(default)]
+    pub group_name: Option<String>,
     pub status: String,
     pub exe_path: Option<String>,
+    #[serde(default)]
+    pub exe_deleted: bool,
+    #[serde(default)]
+    pub is_thread: bool,
+    // Threads in the thread group; 0 when unknown
+    #[serde(default)]
+    pub threads: usize,
+    // Cumulative counters from /proc/<pid>/io; only readable for our own processes unless root
+    #[serde(default)]
+    pub io: Option<IoCounters>,
+    // Per-second rates since the previous snapshot
+    #[serde(default)]
+    pub io_rates: Option<IoRates>,
+    // Why the process would have been left out; only set with --show-filtered
+    #[serde(default)]
+    pub filtered_by: Option<String>,
+}
+
+impl ProcessInfo {
+    pub fn key(&self) -> ProcessKey {
+        ProcessKey {
+            pid: self.pid,
+            start: if self.start_ticks != 0 { self.start_ticks } else { self.start_time },
+        }
+    }
+}
+
+/// I/O accounting of a process since it started, as in `/proc/<pid>/io`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub struct IoCounters {
+    // Bytes passed through read()/write() and similar calls, page cache hits included
+    pub rchar: u64,
+    pub wchar: u64,
+    // Read and write system calls
+    pub syscr: u64,
+    pub syscw: u64,
+    // Bytes fetched from and sent to storage
+    pub read_bytes: u64,
+    pub write_bytes: u64,
+    // Written bytes that never reached storage, e.g. because the file was truncated
+    pub cancelled_write_bytes: u64,
+}
+
+impl IoCounters {
+    /// Average rates between `earlier` and these counters, `seconds` apart
+    pub fn rates_since(&self, earlier: &IoCounters, seconds: f64) -> IoRates {
+        let rate = |now: u64, before: u64| now.saturating_sub(before) as f64 / seconds;
+        IoRates {
+            rchar: rate(self.rchar, earlier.rchar),
+            wchar: rate(self.wchar, earlier.wchar),
+            syscr: rate(self.syscr, earlier.syscr),
+            syscw: rate(self.syscw, earlier.syscw),
+            read_bytes: rate(self.read_bytes, earlier.read_bytes),
+            write_bytes: rate(self.write_bytes, earlier.write_bytes),
+            cancelled_write_bytes: rate(self.cancelled_write_bytes, earlier.cancelled_write_bytes),
+            seconds,
+        }
+    }
+}
+
+/// [`IoCounters`] per second
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+pub struct IoRates {
+    pub rchar: f64,
+    pub wchar: f64,
+    pub syscr: f64,
+    pub syscw: f64,
+    pub read_bytes: f64,
+    pub write_bytes: f64,
+    pub cancelled_write_bytes: f64,
+    // Time between the two readings, so a rate times this is the change since the previous refresh
+    #[serde(default)]
+    pub seconds: f64,
+}
+
+/// Stable identity of a process across refreshes
+///
+/// A PID alone is not enough: the kernel recycles PIDs, so the start time is
+/// part of the key. `start` holds boot-relative clock ticks when available and
+/// falls back to the epoch start time in seconds otherwise. The monitor reads the
+/// ticks once per process and keeps them, so a key never switches between the two.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct ProcessKey {
+    pub pid: u32,
+    pub start: u64,
+}
+
+impl std::fmt::Display for ProcessKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}@{}", self.pid, self.start)
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -20,24 +131,189 @@ pub struct NetworkConnection {
     pub process_name: String,
     pub pid: u32,
     pub local_addr: String,
+    #[serde(default)]
+    pub local_port: u16,
     pub remote_addr: String,
+    #[serde(default)]
+    pub remote_port: u16,
     pub state: String,
     pub protocol: String,
+    #[serde(default)]
+    pub inode: u64,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct MonitorSnapshot {
     pub timestamp: DateTime<Local>,
     pub processes: Vec<ProcessInfo>,
     pub network_connections: Vec<NetworkConnection>,
     pub system_info: SystemSnapshot,
+    #[serde(default)]
+    pub findings: Vec<Finding>,
+    #[serde(default)]
+    pub events: Vec<ProcessEvent>,
+    // Processes left out of (or marked in) this snapshot, per rule
+    #[serde(default)]
+    pub filtered: Vec<FilteredCount>,
+}
+
+impl MonitorSnapshot {
+    /// Give findings from older captures, which stored only a PID, the key of
+    /// that process in this snapshot
+    pub fn resolve_finding_processes(&mut self) {
+        for finding in &mut self.findings {
+            if let Some(key) = &mut finding.process
+                && key.start == 0
+                && let Some(process) = self.processes.iter().find(|p| p.pid == key.pid) {
+                *key = process.key();
+            }
+        }
+    }
+}
+
+/// A process key, or the bare PID findings used to store (start 0 until resolved)
+fn process_or_pid<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<ProcessKey>, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Stored {
+        Key(ProcessKey),
+        Pid(u32),
+    }
+
+    Ok(Option::<Stored>::deserialize(deserializer)?.map(|stored| match stored {
+        Stored::Key(key) => key,
+        Stored::Pid(pid) => ProcessKey { pid, start: 0 },
+    }))
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+/// How many processes one stealth rule or filter excluded from a snapshot
+///
+/// Threads are not counted; they follow their process.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FilteredCount {
+    pub rule: String,
+    pub count: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SystemSnapshot {
     pub total_memory: u64,
     pub used_memory: u64,
     pub cpu_count: usize,
-    pub load_average: f64,
     pub uptime: u64,
-}
\ No newline at end of file
+    // None in captures from before the 5- and 15-minute averages, which only stored `load_average`
+    #[serde(default)]
+    pub load: Option<LoadAverage>,
+    // Pressure stall information; Linux 4.20 and later
+    #[serde(default)]
+    pub pressure: Option<Pressure>,
+    // Utilisation since the previous snapshot
+    #[serde(default)]
+    pub cpu: Option<CpuStats>,
+}
+
+/// Host CPU utilisation over one refresh interval
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CpuStats {
+    pub total: CpuUsage,
+    pub cores: Vec<CoreStats>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct CoreStats {
+    // N of `cpuN`; offline cores are left out, so this is not always the index
+    pub id: u32,
+    pub usage: CpuUsage,
+    // Current frequency in MHz; 0 when unknown
+    pub frequency: u64,
+}
+
+/// Percent of the interval spent in each state, from `/proc/stat`
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+pub struct CpuUsage {
+    pub user: f64,
+    pub nice: f64,
+    pub system: f64,
+    pub irq: f64,
+    pub softirq: f64,
+    // Idle while waiting for I/O
+    pub iowait: f64,
+    // Taken by the hypervisor for other guests
+    pub steal: f64,
+    pub idle: f64,
+}
+
+impl CpuUsage {
+    /// Time spent running anything, user or kernel
+    pub fn busy(&self) -> f64 {
+        self.user + self.nice + self.system + self.irq + self.softirq + self.steal
+    }
+}
+
+/// `/proc/loadavg`
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct LoadAverage {
+    pub one: f64,
+    pub five: f64,
+    pub fifteen: f64,
+    // Tasks currently runnable, and all tasks (threads included); 0 when unknown
+    pub runnable: u32,
+    pub tasks: u32,
+}
+
+/// Share of time tasks were stalled on each resource, from `/proc/pressure/*`
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Pressure {
+    pub cpu: Option<PressureStall>,
+    pub memory: Option<PressureStall>,
+    pub io: Option<PressureStall>,
+}
+
+/// One pressure file: `some` task stalled, or `full`y stalled with no task progressing
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct PressureStall {
+    pub some: PressureAverages,
+    pub full: Option<PressureAverages>,
+}
+
+/// Percent of time stalled over the last 10, 60 and 300 seconds, and the total in microseconds
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct PressureAverages {
+    pub avg10: f64,
+    pub avg60: f64,
+    pub avg300: f64,
+    pub total: u64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum Severity {
+    Low,
+    Medium,
+    High,
+    Critical,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Finding {
+    pub detector: String,
+    pub severity: Severity,
+    #[serde(default, alias = "pid", deserialize_with = "process_or_pid")]
+    pub process: Option<ProcessKey>,
+    pub process_name: Option<String>,
+    pub message: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ProcessEventKind {
+    Started,
+    Exited,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProcessEvent {
+    pub kind: ProcessEventKind,
+    // For exits this is the last state seen before the process disappeared
+    pub process: ProcessInfo,
+    // Seconds between process start and the snapshot that noticed the exit
+    pub lifetime: Option<u64>,
+}
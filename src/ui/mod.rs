@@ -0,0 +1,306 @@
+//! Full-screen terminal interface shared by live monitoring and replay.
+//!
+//! The [`Tui`] owns the terminal (alternate screen, raw mode) and the view state:
+//! which mode is shown, the selected process and scroll position. Callers run
+//! their own event loop, give the keys they do not handle themselves to
+//! [`Tui::handle_key`] and call [`Tui::draw`] whenever something changed.
+
+mod render;
+
+use std::collections::{HashSet, VecDeque};
+use std::io::{self, stdout, Write};
+use std::time::{Duration, Instant};
+
+use chrono::{DateTime, Local};
+use crossterm::{
+    cursor::{Hide, Show},
+    event::{KeyCode, KeyEvent, KeyModifiers},
+    execute,
+    terminal::{self, EnterAlternateScreen, LeaveAlternateScreen},
+};
+
+use crate::columns::{ColumnSpec, DisplayProfile, SortOrder};
+use crate::config::Thresholds;
+use crate::filter::Filter;
+use crate::history::ProcessHistory;
+use crate::types::{MonitorSnapshot, ProcessEvent, ProcessKey};
+
+/// The keys [`Tui::handle_key`] handles, for the footers of the monitor and replay
+macro_rules! view_keys {
+    () => {
+        "↑/↓ PgUp/PgDn: select | /: filter | o/r: sort/reverse | t: tree | n: network | d: details | u: CPUs | v: verbose"
+    };
+}
+pub(crate) use view_keys;
+
+/// Alerts kept in the alert panel history
+const MAX_ALERTS: usize = 100;
+
+/// Samples kept per process for the sparklines; two minutes at the default interval
+pub const HISTORY_SAMPLES: usize = 60;
+
+/// How long a status message stays in the footer
+const MESSAGE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Everything needed to draw one screen
+pub struct Frame<'a> {
+    pub snapshot: &'a MonitorSnapshot,
+    // Shown next to the timestamp in the title bar, e.g. stealth counts or replay position
+    pub status: String,
+    // Key reminder shown in the footer
+    pub help: &'a str,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ViewMode {
+    Processes,
+    Tree,
+    Network,
+}
+
+pub struct ViewState {
+    pub mode: ViewMode,
+    pub selected: Option<ProcessKey>,
+    // Row of the selection, used to keep a nearby row selected when the process exits
+    selected_row: usize,
+    network_selected: usize,
+    scroll: usize,
+    // Rows available to the list in the last drawn frame, used for paging
+    page_size: usize,
+    pub collapsed: HashSet<u32>,
+    pub show_detail: bool,
+    // Per-core CPU bars under the header
+    pub show_cpu: bool,
+    pub verbose: bool,
+    pub columns: Vec<ColumnSpec>,
+    pub sort: SortOrder,
+    pub thresholds: Thresholds,
+    // Recent CPU and memory of each process, for the sparklines
+    history: ProcessHistory,
+    // Active filter expression, shown in the header
+    filter: String,
+}
+
+pub struct Tui {
+    pub view: ViewState,
+    alerts: VecDeque<(DateTime<Local>, ProcessEvent)>,
+    show_alerts: bool,
+    message: Option<(String, Instant)>,
+    // Filter being edited after `/`
+    prompt: Option<String>,
+    filter_change: Option<Option<Filter>>,
+}
+
+impl Tui {
+    /// Switch to the alternate screen in raw mode; the terminal is restored on drop
+    pub fn enter(verbose: bool, show_alerts: bool, profile: DisplayProfile, thresholds: Thresholds) -> io::Result<Self> {
+        let tui = Self {
+            view: ViewState {
+                mode: ViewMode::Processes,
+                selected: None,
+                selected_row: 0,
+                network_selected: 0,
+                scroll: 0,
+                page_size: 10,
+                collapsed: HashSet::new(),
+                show_detail: true,
+                show_cpu: true,
+                verbose,
+                columns: profile.columns,
+                sort: profile.sort,
+                thresholds,
+                history: ProcessHistory::new(HISTORY_SAMPLES),
+                filter: String::new(),
+            },
+            alerts: VecDeque::new(),
+            show_alerts,
+            message: None,
+            prompt: None,
+            filter_change: None,
+        };
+        tui.resume()?;
+        Ok(tui)
+    }
+
+    /// Hand the terminal back temporarily, e.g. for an interactive prompt
+    pub fn suspend(&self) -> io::Result<()> {
+        terminal::disable_raw_mode()?;
+        execute!(stdout(), Show, LeaveAlternateScreen)
+    }
+
+    pub fn resume(&self) -> io::Result<()> {
+        terminal::enable_raw_mode()?;
+        execute!(stdout(), EnterAlternateScreen, Hide)
+    }
+
+    /// Append the process events of a new snapshot to the alert panel
+    pub fn record_events(&mut self, snapshot: &MonitorSnapshot) {
+        for event in &snapshot.events {
+            if self.alerts.len() == MAX_ALERTS {
+                self.alerts.pop_back();
+            }
+            self.alerts.push_front((snapshot.timestamp, event.clone()));
+        }
+    }
+
+    pub fn clear_alerts(&mut self) {
+        self.alerts.clear();
+    }
+
+    /// Add the CPU and memory figures of a new snapshot to the sparklines
+    pub fn record_history(&mut self, snapshot: &MonitorSnapshot) {
+        self.view.history.record(snapshot);
+    }
+
+    /// Start the sparklines over, e.g. after jumping to another point of a capture
+    pub fn clear_history(&mut self) {
+        self.view.history.clear();
+    }
+
+    /// Show a message in the footer for a few seconds
+    pub fn set_message(&mut self, message: impl Into<String>) {
+        self.message = Some((message.into(), Instant::now()));
+    }
+
+    /// Show the filter the caller is applying
+    pub fn set_filter(&mut self, filter: Option<&Filter>) {
+        self.view.filter = filter.map_or(String::new(), |f| f.to_string());
+    }
+
+    /// Whether the filter prompt is open; it then takes every key, including `q`
+    pub fn is_editing(&self) -> bool {
+        self.prompt.is_some()
+    }
+
+    /// A filter entered at the prompt since the last call; `Some(None)` clears the filter
+    pub fn take_filter_change(&mut self) -> Option<Option<Filter>> {
+        self.filter_change.take()
+    }
+
+    /// Handle navigation and view keys; returns false for keys left to the caller
+    pub fn handle_key(&mut self, key: KeyEvent, snapshot: &MonitorSnapshot) -> bool {
+        if self.prompt.is_some() {
+            self.edit_prompt(key);
+            return true;
+        }
+
+        let page = self.view.page_size.max(1) as isize;
+
+        match key.code {
+            KeyCode::Up | KeyCode::Char('k') => self.move_selection(snapshot, -1),
+            KeyCode::Down | KeyCode::Char('j') => self.move_selection(snapshot, 1),
+            KeyCode::PageUp => self.move_selection(snapshot, -page),
+            KeyCode::PageDown => self.move_selection(snapshot, page),
+            KeyCode::Home => self.move_selection(snapshot, isize::MIN / 2),
+            KeyCode::End => self.move_selection(snapshot, isize::MAX / 2),
+            KeyCode::Char('t') => {
+                self.view.mode = if self.view.mode == ViewMode::Tree { ViewMode::Processes } else { ViewMode::Tree };
+                self.view.scroll = 0;
+            }
+            KeyCode::Char('n') => {
+                self.view.mode = if self.view.mode == ViewMode::Network { ViewMode::Processes } else { ViewMode::Network };
+                self.view.scroll = 0;
+            }
+            KeyCode::Enter | KeyCode::Char(' ') if self.view.mode == ViewMode::Tree => {
+                if let Some(key) = self.view.selected
+                    && !self.view.collapsed.remove(&key.pid) {
+                    self.view.collapsed.insert(key.pid);
+                }
+            }
+            KeyCode::Char('o') => {
+                self.view.sort.key = self.view.sort.key.next();
+                self.view.sort.reverse = false;
+                self.set_message(format!("Sort: {} {}", self.view.sort.key, self.view.sort.indicator()));
+            }
+            KeyCode::Char('r') => {
+                self.view.sort.reverse = !self.view.sort.reverse;
+                self.set_message(format!("Sort: {} {}", self.view.sort.key, self.view.sort.indicator()));
+            }
+            KeyCode::Char('/') => self.prompt = Some(self.view.filter.clone()),
+            KeyCode::Char('d') => self.view.show_detail = !self.view.show_detail,
+            KeyCode::Char('u') => self.view.show_cpu = !self.view.show_cpu,
+            KeyCode::Char('v') => self.view.verbose = !self.view.verbose,
+            _ => return false,
+        }
+        true
+    }
+
+    fn edit_prompt(&mut self, key: KeyEvent) {
+        let Some(buffer) = &mut self.prompt else {
+            return;
+        };
+
+        match key.code {
+            KeyCode::Esc => self.prompt = None,
+            KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => self.prompt = None,
+            KeyCode::Char('u') if key.modifiers.contains(KeyModifiers::CONTROL) => buffer.clear(),
+            KeyCode::Backspace => {
+                buffer.pop();
+            }
+            KeyCode::Char(c) => buffer.push(c),
+            KeyCode::Enter if buffer.trim().is_empty() => {
+                self.filter_change = Some(None);
+                self.view.filter.clear();
+                self.prompt = None;
+            }
+            // Invalid expressions keep the prompt open so they can be fixed
+            KeyCode::Enter => match Filter::parse(buffer) {
+                Ok(filter) => {
+                    self.view.filter = filter.to_string();
+                    self.filter_change = Some(Some(filter));
+                    self.prompt = None;
+                    self.message = None;
+                }
+                Err(e) => self.set_message(format!("Invalid filter: {}", e)),
+            },
+            _ => {}
+        }
+    }
+
+    fn move_selection(&mut self, snapshot: &MonitorSnapshot, delta: isize) {
+        if self.view.mode == ViewMode::Network {
+            let last = snapshot.network_connections.len().saturating_sub(1) as isize;
+            self.view.network_selected = (self.view.network_selected as isize + delta).clamp(0, last) as usize;
+            return;
+        }
+
+        let rows = render::process_rows(snapshot, &self.view);
+        if rows.is_empty() {
+            return;
+        }
+        let current = render::selected_index(&rows, &self.view) as isize;
+        let next = (current + delta).clamp(0, rows.len() as isize - 1) as usize;
+        self.view.selected = Some(rows[next].process.key());
+        self.view.selected_row = next;
+    }
+
+    pub fn draw(&mut self, frame: &Frame) -> io::Result<()> {
+        if self.message.as_ref().is_some_and(|(_, at)| at.elapsed() > MESSAGE_TIMEOUT) {
+            self.message = None;
+        }
+
+        let (width, height) = terminal::size()?;
+        let alerts = if self.show_alerts { Some(&self.alerts) } else { None };
+        let message = match (&self.prompt, &self.message) {
+            (Some(buffer), Some((message, _))) => Some(format!("Filter: {}▏ | {}", buffer, message)),
+            (Some(buffer), None) => Some(format!("Filter: {}▏ | Enter: apply, Esc: cancel", buffer)),
+            (None, message) => message.as_ref().map(|(m, _)| m.clone()),
+        };
+
+        let mut out = stdout().lock();
+        render::draw(&mut out, frame, &mut self.view, alerts, message.as_deref(), width as usize, height as usize)?;
+        out.flush()
+    }
+}
+
+impl Drop for Tui {
+    fn drop(&mut self) {
+        let _ = self.suspend();
+    }
+}
+
+/// `q`, `Esc` and `Ctrl-C` all quit; raw mode delivers Ctrl-C as a key press
+pub fn is_quit(key: &KeyEvent) -> bool {
+    matches!(key.code, KeyCode::Char('q') | KeyCode::Esc)
+        || (key.code == KeyCode::Char('c') && key.modifiers.contains(KeyModifiers::CONTROL))
+}
@@ -0,0 +1,570 @@
+//! Drawing of a [`Frame`] into the terminal, one full-width line at a time.
+//!
+//! Every line is padded to the terminal width, so a redraw overwrites the
+//! previous frame in place without clearing the screen first.
+
+use std::collections::{HashSet, VecDeque};
+use std::io::{self, Write};
+
+use chrono::{DateTime, Local, TimeZone};
+use crossterm::{
+    cursor::MoveTo,
+    queue,
+    style::{Color, PrintStyledContent, Stylize},
+};
+
+use super::{Frame, ViewMode, ViewState};
+use crate::columns::{display_id, Column, ColumnSpec};
+use crate::config::Thresholds;
+use crate::history::{sparkline, ProcessHistory, Stats, MIN_CPU_SCALE};
+use crate::network;
+use crate::tree::{ProcessTree, TreeRow};
+use crate::types::{CpuStats, CpuUsage, IoRates, MonitorSnapshot, ProcessEvent, ProcessEventKind, ProcessInfo, ProcessKey, Severity, SystemSnapshot};
+use crate::utils::{fit_width, format_duration, format_size};
+
+/// Rows given to the findings and alert panels at most
+const PANEL_ROWS: usize = 4;
+
+/// Rows of the detail pane, including its title
+const DETAIL_ROWS: usize = 8;
+
+/// Rows of per-core bars before the cells get narrower than `CORE_CELL`
+const CPU_ROWS: usize = 4;
+
+/// Preferred width of one core's cell in the CPU panel
+const CORE_CELL: usize = 32;
+
+/// Characters around a core's bar: ` 12 [`, `] 100%`
+const CORE_LABEL: usize = 11;
+
+/// Narrowest core bar before the grid takes more rows
+const MIN_BAR: usize = 5;
+
+/// Samples shown in each sparkline of the detail pane
+const HISTORY_WIDTH: usize = 30;
+
+/// Width of the whole-host bar in the CPU panel title
+const TOTAL_BAR: usize = 20;
+
+/// The list never shrinks below this; panels are dropped first
+const MIN_LIST_ROWS: usize = 5;
+
+/// Rows of the process list in the current mode: all processes in sort order, or the tree
+pub(super) fn process_rows<'a>(snapshot: &'a MonitorSnapshot, view: &ViewState) -> Vec<TreeRow<'a>> {
+    if view.mode == ViewMode::Tree {
+        return ProcessTree::build(&snapshot.processes).rows(&view.collapsed);
+    }
+
+    let mut processes: Vec<&ProcessInfo> = snapshot.processes.iter().filter(|p| !p.is_thread).collect();
+    processes.sort_by(|a, b| view.sort.compare(a, b));
+
+    processes.into_iter()
+        .map(|process| TreeRow {
+            process,
+            prefix: String::new(),
+            has_children: false,
+            collapsed: false,
+            unusual: None,
+        })
+        .collect()
+}
+
+/// Index of the selected process, or the nearest row if it has gone away
+pub(super) fn selected_index(rows: &[TreeRow], view: &ViewState) -> usize {
+    view.selected
+        .and_then(|key| rows.iter().position(|row| row.process.key() == key))
+        .unwrap_or(view.selected_row)
+        .min(rows.len().saturating_sub(1))
+}
+
+struct Screen<'w, W: Write> {
+    out: &'w mut W,
+    width: usize,
+    y: u16,
+}
+
+impl<W: Write> Screen<'_, W> {
+    fn line(&mut self, text: &str, color: Option<Color>, reverse: bool) -> io::Result<()> {
+        let mut styled = fit_width(text, self.width).stylize();
+        if let Some(color) = color {
+            styled = styled.with(color);
+        }
+        if reverse {
+            styled = styled.reverse();
+        }
+        queue!(self.out, MoveTo(0, self.y), PrintStyledContent(styled))?;
+        self.y += 1;
+        Ok(())
+    }
+
+    fn title(&mut self, text: &str) -> io::Result<()> {
+        self.line(text, Some(Color::Cyan), false)
+    }
+}
+
+pub(super) fn draw<W: Write>(out: &mut W, frame: &Frame, view: &mut ViewState,
+                             alerts: Option<&VecDeque<(DateTime<Local>, ProcessEvent)>>,
+                             message: Option<&str>, width: usize, height: usize) -> io::Result<()> {
+    let snapshot = frame.snapshot;
+    let rows = process_rows(snapshot, view);
+
+    // Pin the selection to a concrete process so it survives re-sorting
+    let selected = (!rows.is_empty()).then(|| selected_index(&rows, view));
+    if let Some(index) = selected {
+        view.selected = Some(rows[index].process.key());
+        view.selected_row = index;
+    }
+    view.network_selected = view.network_selected.min(snapshot.network_connections.len().saturating_sub(1));
+
+    let detail_process = match view.mode {
+        ViewMode::Network => snapshot.network_connections.get(view.network_selected)
+            .and_then(|conn| snapshot.processes.iter().find(|p| p.pid == conn.pid)),
+        _ => selected.map(|i| rows[i].process),
+    };
+    let alerts = alerts.filter(|a| !a.is_empty());
+
+    // Title, system and load lines, column header and footer are always shown
+    let fixed = 5;
+    let mut detail = view.show_detail && detail_process.is_some();
+    let mut findings = !snapshot.findings.is_empty();
+    let mut alert_panel = alerts.is_some();
+    let cpu_stats = snapshot.system_info.cpu.as_ref().filter(|_| view.show_cpu);
+    let mut cpu_panel = cpu_stats.is_some();
+    let cpu_height = cpu_stats.map_or(0, |cpu| 1 + core_grid(cpu.cores.len(), width).1);
+    let panel_height = |detail: bool, findings: bool, alert_panel: bool, cpu_panel: bool| {
+        detail as usize * DETAIL_ROWS
+            + findings as usize * (1 + snapshot.findings.len().min(PANEL_ROWS))
+            + alert_panel as usize * (1 + alerts.map_or(0, |a| a.len().min(PANEL_ROWS)))
+            + cpu_panel as usize * cpu_height
+    };
+
+    // Give the list priority on small terminals
+    while (detail || findings || alert_panel || cpu_panel)
+        && height < fixed + MIN_LIST_ROWS + panel_height(detail, findings, alert_panel, cpu_panel) {
+        if alert_panel {
+            alert_panel = false;
+        } else if findings {
+            findings = false;
+        } else if cpu_panel {
+            cpu_panel = false;
+        } else {
+            detail = false;
+        }
+    }
+    let list_height = height.saturating_sub(fixed + panel_height(detail, findings, alert_panel, cpu_panel)).max(1);
+    view.page_size = list_height;
+
+    let mut screen = Screen { out, width, y: 0 };
+
+    screen.title(&format!("🔍 RProcMon [{}] {}", snapshot.timestamp.format("%Y-%m-%d %H:%M:%S"), frame.status))?;
+    let system = &snapshot.system_info;
+    let filter_label = if view.filter.is_empty() { String::new() } else { format!(" | Filter: {}", view.filter) };
+    let filtered: usize = snapshot.filtered.iter().map(|f| f.count).sum();
+    let filtered_label = if filtered == 0 { String::new() } else { format!(" | Filtered: {}", filtered) };
+    screen.line(&format!("Memory: {:.1}% ({}/{} MB) | CPUs: {} | Uptime: {} | Processes: {} | Connections: {}{}{}",
+                         system.used_memory as f64 / system.total_memory.max(1) as f64 * 100.0,
+                         system.used_memory / 1_048_576,
+                         system.total_memory / 1_048_576,
+                         system.cpu_count,
+                         format_duration(system.uptime),
+                         snapshot.processes.iter().filter(|p| !p.is_thread && p.filtered_by.is_none()).count(),
+                         snapshot.network_connections.len(),
+                         filtered_label,
+                         filter_label), None, false)?;
+    draw_load(&mut screen, system, &view.thresholds)?;
+    if cpu_panel && let Some(cpu) = cpu_stats {
+        draw_cpus(&mut screen, cpu)?;
+    }
+
+    match view.mode {
+        ViewMode::Network => draw_network(&mut screen, snapshot, view, list_height)?,
+        _ => draw_processes(&mut screen, snapshot, view, &rows, selected, list_height)?,
+    }
+
+    if detail && let Some(process) = detail_process {
+        draw_detail(&mut screen, snapshot, &view.history, process)?;
+    }
+    if findings {
+        draw_findings(&mut screen, snapshot)?;
+    }
+    if alert_panel && let Some(alerts) = alerts {
+        draw_alerts(&mut screen, alerts)?;
+    }
+
+    // Whatever is left above the footer, e.g. after the panels shrank
+    while (screen.y as usize) < height.saturating_sub(1) {
+        screen.line("", None, false)?;
+    }
+
+    // Keep clear of the last column so the terminal never scrolls
+    screen.width = width.saturating_sub(1);
+    match message {
+        Some(message) => screen.line(message, Some(Color::Yellow), true),
+        None => screen.line(frame.help, None, true),
+    }
+}
+
+/// Move the window so that `selected` is visible, returning the first visible row
+fn scroll_to(scroll: &mut usize, selected: usize, total: usize, height: usize) -> usize {
+    if selected < *scroll {
+        *scroll = selected;
+    } else if selected >= *scroll + height {
+        *scroll = selected + 1 - height;
+    }
+    *scroll = (*scroll).min(total.saturating_sub(height));
+    *scroll
+}
+
+/// Configured columns; the tree view draws the name column last so the tree has room to grow
+fn visible_columns(view: &ViewState) -> Vec<ColumnSpec> {
+    let mut columns = view.columns.clone();
+    if view.mode == ViewMode::Tree {
+        columns.retain(|c| c.column != Column::Name);
+        columns.push(Column::Name.into());
+    }
+    columns
+}
+
+/// Join cells at their column widths; the last cell is left to run to the edge
+fn join_cells(columns: &[ColumnSpec], cells: impl Iterator<Item = String>) -> String {
+    let mut line = String::new();
+    for (i, (spec, cell)) in columns.iter().zip(cells).enumerate() {
+        if i + 1 == columns.len() {
+            line.push_str(&cell);
+        } else {
+            line.push_str(&fit_width(&cell, spec.width));
+            line.push(' ');
+        }
+    }
+    line
+}
+
+/// Load averages, task counts and pressure, red when a pressure threshold is exceeded
+fn draw_load<W: Write>(screen: &mut Screen<W>, system: &SystemSnapshot, thresholds: &Thresholds) -> io::Result<()> {
+    let load = match &system.load {
+        Some(load) if load.tasks > 0 => format!("Load: {:.2} {:.2} {:.2} | Tasks: {} running / {}",
+                                                load.one, load.five, load.fifteen, load.runnable, load.tasks),
+        Some(load) => format!("Load: {:.2} {:.2} {:.2}", load.one, load.five, load.fifteen),
+        None => "Load: -".to_string(),
+    };
+
+    let Some(pressure) = &system.pressure else {
+        return screen.line(&load, None, false);
+    };
+    let resources = [
+        ("cpu", pressure.cpu, thresholds.cpu_pressure),
+        ("memory", pressure.memory, thresholds.memory_pressure),
+        ("io", pressure.io, thresholds.io_pressure),
+    ];
+    let stalls: Vec<String> = resources.iter()
+        .map(|(resource, stall, _)| match stall {
+            Some(stall) => format!("{} {:.1}%", resource, stall.some.avg10),
+            None => format!("{} -", resource),
+        })
+        .collect();
+    let exceeded = resources.iter().any(|(_, stall, threshold)| {
+        stall.zip(*threshold).is_some_and(|(stall, threshold)| stall.some.avg10 > threshold)
+    });
+    screen.line(&format!("{} | Pressure (10s): {}", load, stalls.join(" ")),
+                exceeded.then_some(Color::Red), false)
+}
+
+/// Bars for the whole host and for each core: `|` user, `#` system, `w` iowait, `~` steal
+fn draw_cpus<W: Write>(screen: &mut Screen<W>, cpu: &CpuStats) -> io::Result<()> {
+    let total = &cpu.total;
+    let frequencies: Vec<u64> = cpu.cores.iter().map(|c| c.frequency).filter(|&f| f > 0).collect();
+    let frequency = if frequencies.is_empty() {
+        String::new()
+    } else {
+        format!(" | {:.2} GHz", frequencies.iter().sum::<u64>() as f64 / frequencies.len() as f64 / 1000.0)
+    };
+    screen.title(&format!("📊 CPU [{}] {:5.1}% | user {:.1}% | system {:.1}% | iowait {:.1}% | steal {:.1}%{}",
+                          usage_bar(total, TOTAL_BAR),
+                          total.busy(),
+                          total.user + total.nice,
+                          total.system + total.irq + total.softirq,
+                          total.iowait,
+                          total.steal,
+                          frequency))?;
+
+    let (per_row, rows) = core_grid(cpu.cores.len(), screen.width);
+    let bar = (screen.width / per_row).min(CORE_CELL).saturating_sub(CORE_LABEL + 1).max(1);
+    for row in 0..rows {
+        let cells: Vec<String> = cpu.cores.iter()
+            .skip(row * per_row)
+            .take(per_row)
+            .map(|core| format!("{:>3} [{}] {:>3.0}%", core.id, usage_bar(&core.usage, bar), core.usage.busy()))
+            .collect();
+        screen.line(&cells.join(" "), None, false)?;
+    }
+    Ok(())
+}
+
+/// Cores per row and rows of the core grid: up to `CPU_ROWS` rows of cells about
+/// `CORE_CELL` wide, narrower cells (and then more rows) on hosts with many cores
+fn core_grid(cores: usize, width: usize) -> (usize, usize) {
+    let widest = (width / CORE_CELL).max(1).min(cores.max(1));
+    let narrowest = (width / (CORE_LABEL + 1 + MIN_BAR)).max(1);
+    let per_row = cores.div_ceil(CPU_ROWS).max(widest).min(narrowest);
+    (per_row, cores.div_ceil(per_row))
+}
+
+fn usage_bar(usage: &CpuUsage, width: usize) -> String {
+    let segments = [
+        (usage.user + usage.nice, '|'),
+        (usage.system + usage.irq + usage.softirq, '#'),
+        (usage.iowait, 'w'),
+        (usage.steal, '~'),
+    ];
+    let mut bar = String::with_capacity(width);
+    let (mut filled, mut drawn) = (0.0, 0);
+    for (percent, c) in segments {
+        filled += percent;
+        // Round the running total so the segments always add up to the whole
+        let end = ((filled / 100.0 * width as f64).round() as usize).min(width);
+        bar.extend(std::iter::repeat_n(c, end.saturating_sub(drawn)));
+        drawn = drawn.max(end);
+    }
+    bar.extend(std::iter::repeat_n(' ', width - drawn));
+    bar
+}
+
+fn draw_processes<W: Write>(screen: &mut Screen<W>, snapshot: &MonitorSnapshot, view: &mut ViewState,
+                            rows: &[TreeRow], selected: Option<usize>, height: usize) -> io::Result<()> {
+    let flagged: HashSet<ProcessKey> = snapshot.findings.iter().filter_map(|f| f.process).collect();
+    let tree = view.mode == ViewMode::Tree;
+    let columns = visible_columns(view);
+
+    let headers = columns.iter().map(|spec| match spec.column {
+        Column::Name if tree => "TREE".to_string(),
+        column if !tree && column == view.sort.key.column() => format!("{}{}", column.header(), view.sort.indicator()),
+        column => column.header().to_string(),
+    });
+    screen.line(&format!(" {}", join_cells(&columns, headers)), None, true)?;
+
+    let start = scroll_to(&mut view.scroll, selected.unwrap_or(0), rows.len(), height);
+    let now = snapshot.timestamp.timestamp().max(0) as u64;
+
+    for i in start..start + height {
+        let Some(row) = rows.get(i) else {
+            screen.line("", None, false)?;
+            continue;
+        };
+        let process = row.process;
+        let marker = if flagged.contains(&process.key()) { "!" } else { " " };
+
+        let cells = columns.iter().map(|spec| match spec.column {
+            Column::Name if tree => {
+                let fold = match (row.has_children, row.collapsed) {
+                    (true, true) => "[+] ",
+                    (true, false) => "[-] ",
+                    _ => "",
+                };
+                format!("{}{}{}", row.prefix, fold, process.name)
+            }
+            Column::CpuHistory => sparkline(&view.history.cpu(&process.key()), spec.width, MIN_CPU_SCALE),
+            Column::MemHistory => sparkline(&view.history.memory(&process.key()), spec.width, 0.0),
+            column => column.value(process, now),
+        });
+        let mut text = format!("{}{}", marker, join_cells(&columns, cells));
+
+        if let Some(reason) = &row.unusual {
+            text.push_str(&format!("  ⚠ {}", reason));
+        } else if view.verbose && !process.cmd.is_empty() && !columns.iter().any(|c| c.column == Column::Cmd) {
+            text.push_str(&format!(" {}", process.cmd.join(" ")));
+        }
+
+        // Grey out filtered processes, highlight unusual lineage and high CPU usage
+        let color = if process.filtered_by.is_some() {
+            Some(Color::DarkGrey)
+        } else if row.unusual.is_some() || process.cpu_usage > view.thresholds.cpu_critical {
+            Some(Color::Red)
+        } else if process.cpu_usage > view.thresholds.cpu_warning {
+            Some(Color::Yellow)
+        } else {
+            None
+        };
+        screen.line(&text, color, selected == Some(i))?;
+    }
+    Ok(())
+}
+
+fn draw_network<W: Write>(screen: &mut Screen<W>, snapshot: &MonitorSnapshot, view: &mut ViewState,
+                          height: usize) -> io::Result<()> {
+    screen.line(&format!(" {:<14} {:<30} {:<30} {:<12} {:<8} {}",
+                         "PROTO", "LOCAL", "REMOTE", "STATE", "PID", "PROCESS"), None, true)?;
+
+    let connections = &snapshot.network_connections;
+    if connections.is_empty() {
+        screen.line(" No sockets collected (start with -n to enable network monitoring)", None, false)?;
+        for _ in 1..height {
+            screen.line("", None, false)?;
+        }
+        return Ok(());
+    }
+
+    let start = scroll_to(&mut view.scroll, view.network_selected, connections.len(), height);
+    for i in start..start + height {
+        let Some(conn) = connections.get(i) else {
+            screen.line("", None, false)?;
+            continue;
+        };
+        let (local, remote) = network::connection_endpoints(conn);
+        screen.line(&format!(" {:<14} {} {} {:<12} {:<8} {}",
+                             conn.protocol,
+                             fit_width(&local, 30),
+                             fit_width(&remote, 30),
+                             conn.state,
+                             if conn.pid == 0 { "-".to_string() } else { conn.pid.to_string() },
+                             conn.process_name), None, view.network_selected == i)?;
+    }
+    Ok(())
+}
+
+fn draw_detail<W: Write>(screen: &mut Screen<W>, snapshot: &MonitorSnapshot, history: &ProcessHistory,
+                         process: &ProcessInfo) -> io::Result<()> {
+    let key = process.key();
+    let now = snapshot.timestamp.timestamp().max(0) as u64;
+    let started = Local.timestamp_opt(process.start_time as i64, 0)
+        .single()
+        .map_or("-".to_string(), |t| t.format("%Y-%m-%d %H:%M:%S").to_string());
+
+    let ancestry: Vec<String> = ProcessTree::build(&snapshot.processes)
+        .ancestry(process.pid)
+        .iter()
+        .map(|p| format!("{}({})", p.name, p.pid))
+        .collect();
+
+    let sockets: Vec<String> = snapshot.network_connections.iter()
+        .filter(|c| c.pid == process.pid)
+        .map(|c| {
+            let (local, remote) = network::connection_endpoints(c);
+            format!("{} {} -> {} {}", c.protocol, local, remote, c.state)
+        })
+        .collect();
+
+    let findings: Vec<String> = snapshot.findings.iter()
+        .filter(|f| f.process == Some(key))
+        .map(|f| format!("{} {}: {}", format!("{:?}", f.severity).to_uppercase(), f.detector, f.message))
+        .collect();
+
+    let mut lines = vec![
+        format!(" PPID: {} | Threads: {} | CPU: {:.1}% | Memory: {} KB | Started: {} ({} ago)",
+                optional(process.parent_pid),
+                Column::Threads.value(process, now),
+                process.cpu_usage,
+                process.memory / 1024,
+                started,
+                format_duration(now.saturating_sub(process.start_time))),
+        format!(" User: {} ({}) | EUID: {} | SUID: {} | Group: {} ({}) | EGID: {}",
+                display_id(process.user_name.as_deref(), process.user_id),
+                optional(process.user_id),
+                optional(process.effective_user_id),
+                optional(process.saved_user_id),
+                display_id(process.group_name.as_deref(), process.group_id),
+                optional(process.group_id),
+                optional(process.effective_group_id)),
+        format!(" EXE: {}{}",
+                process.exe_path.as_deref().unwrap_or("-"),
+                if process.exe_deleted { " (deleted)" } else { "" }),
+        format!(" CMD: {}", if process.cmd.is_empty() { "-".to_string() } else { process.cmd.join(" ") }),
+        format!(" Ancestry: {}", ancestry.join(" → ")),
+    ];
+    let cpu = history.cpu(&key);
+    let memory = history.memory(&key);
+    if let (Some(cpu_stats), Some(memory_stats)) = (Stats::of(&cpu), Stats::of(&memory)) {
+        let width = cpu.len().min(HISTORY_WIDTH);
+        lines.push(format!(" History ({} samples): CPU {} min {:.1}% avg {:.1}% max {:.1}% | RSS {} min {} avg {} max {}",
+                           cpu.len(),
+                           sparkline(&cpu, width, MIN_CPU_SCALE),
+                           cpu_stats.min,
+                           cpu_stats.avg,
+                           cpu_stats.max,
+                           sparkline(&memory, width, 0.0),
+                           format_size(memory_stats.min),
+                           format_size(memory_stats.avg),
+                           format_size(memory_stats.max)));
+    }
+    if let Some(io) = &process.io {
+        let rate = |field: fn(&IoRates) -> f64, format: fn(f64) -> String| {
+            process.io_rates.as_ref().map_or("-".to_string(), |r| format(field(r)))
+        };
+        lines.push(format!(" I/O: read {}/s ({} total) | write {}/s ({} total) | syscalls {}/s read, {}/s write | cancelled {}",
+                           rate(|r| r.read_bytes, format_size),
+                           format_size(io.read_bytes as f64),
+                           rate(|r| r.write_bytes, format_size),
+                           format_size(io.write_bytes as f64),
+                           rate(|r| r.syscr, |n| format!("{:.0}", n)),
+                           rate(|r| r.syscw, |n| format!("{:.0}", n)),
+                           format_size(io.cancelled_write_bytes as f64)));
+    }
+    if !sockets.is_empty() {
+        lines.push(format!(" Sockets ({}): {}", sockets.len(), sockets.join(", ")));
+    }
+    lines.extend(findings.into_iter().map(|f| format!(" Finding: {}", f)));
+
+    let filtered = process.filtered_by.as_ref().map_or(String::new(), |rule| format!(" | Filtered by {}", rule));
+    screen.title(&format!("📋 {} (PID {}) {}{}", process.name, process.pid, process.status, filtered))?;
+    for i in 0..DETAIL_ROWS - 1 {
+        let text = lines.get(i).map_or("", |l| l.as_str());
+        let color = text.starts_with(" Finding").then_some(Color::Red);
+        screen.line(text, color, false)?;
+    }
+    Ok(())
+}
+
+fn draw_findings<W: Write>(screen: &mut Screen<W>, snapshot: &MonitorSnapshot) -> io::Result<()> {
+    screen.title(&format!("🚩 Security Findings ({})", snapshot.findings.len()))?;
+
+    // Findings are sorted by severity, so the most severe ones make the cut
+    for finding in snapshot.findings.iter().take(PANEL_ROWS) {
+        let color = match finding.severity {
+            Severity::Critical | Severity::High => Color::Red,
+            Severity::Medium => Color::Yellow,
+            Severity::Low => Color::White,
+        };
+        screen.line(&format!(" {:<10} {:<20} {:<8} {} {}",
+                             format!("{:?}", finding.severity).to_uppercase(),
+                             finding.detector,
+                             finding.process.map_or("-".to_string(), |k| k.pid.to_string()),
+                             fit_width(finding.process_name.as_deref().unwrap_or("-"), 20),
+                             finding.message), Some(color), false)?;
+    }
+    Ok(())
+}
+
+fn draw_alerts<W: Write>(screen: &mut Screen<W>, alerts: &VecDeque<(DateTime<Local>, ProcessEvent)>) -> io::Result<()> {
+    screen.title(&format!("🚨 Process Alerts ({})", alerts.len()))?;
+
+    for (timestamp, event) in alerts.iter().take(PANEL_ROWS) {
+        let process = &event.process;
+        let (text, color) = match event.kind {
+            ProcessEventKind::Started => (
+                format!(" [{}] started {} (PID: {}, PPID: {}) {}",
+                        timestamp.format("%H:%M:%S"),
+                        process.name,
+                        process.pid,
+                        optional(process.parent_pid),
+                        process.cmd.join(" ")),
+                Color::Green,
+            ),
+            ProcessEventKind::Exited => (
+                format!(" [{}] exited  {} (PID: {}, PPID: {}) lived {} | last CPU: {:.1}% | last MEM: {} KB",
+                        timestamp.format("%H:%M:%S"),
+                        process.name,
+                        process.pid,
+                        optional(process.parent_pid),
+                        format_duration(event.lifetime.unwrap_or(0)),
+                        process.cpu_usage,
+                        process.memory / 1024),
+                Color::Magenta,
+            ),
+        };
+        screen.line(&text, Some(color), false)?;
+    }
+    Ok(())
+}
+
+fn optional<T: ToString>(value: Option<T>) -> String {
+    value.map_or("-".to_string(), |v| v.to_string())
+}
@@ -0,0 +1,100 @@
+//! User and group names from `/etc/passwd` and `/etc/group`.
+//!
+//! The files are parsed once and re-read only when their modification time
+//! changes, so resolving names on every refresh stays cheap.
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+/// `id -> name` table backed by one colon-separated database file
+struct NameTable {
+    path: PathBuf,
+    modified: Option<SystemTime>,
+    names: HashMap<u32, String>,
+}
+
+impl NameTable {
+    fn new(path: impl Into<PathBuf>) -> Self {
+        let mut table = Self {
+            path: path.into(),
+            modified: None,
+            names: HashMap::new(),
+        };
+        table.refresh();
+        table
+    }
+
+    /// Re-read the file if it changed since the last load
+    fn refresh(&mut self) {
+        let modified = fs::metadata(&self.path).and_then(|m| m.modified()).ok();
+        if modified.is_some() && modified == self.modified {
+            return;
+        }
+        self.modified = modified;
+        self.names = read_names(&self.path);
+    }
+
+    fn name(&self, id: u32) -> Option<&str> {
+        self.names.get(&id).map(String::as_str)
+    }
+
+    fn id(&self, name: &str) -> Option<u32> {
+        self.names.iter().find(|(_, n)| *n == name).map(|(id, _)| *id)
+    }
+}
+
+/// Both files use `name:password:id:...`; the first entry wins for duplicate ids
+fn read_names(path: &Path) -> HashMap<u32, String> {
+    let mut names = HashMap::new();
+    let Ok(content) = fs::read_to_string(path) else {
+        return names;
+    };
+
+    for line in content.lines() {
+        if line.starts_with('#') {
+            continue;
+        }
+        let mut fields = line.split(':');
+        let (Some(name), Some(_), Some(id)) = (fields.next(), fields.next(), fields.next()) else {
+            continue;
+        };
+        if let Ok(id) = id.parse() {
+            names.entry(id).or_insert_with(|| name.to_string());
+        }
+    }
+    names
+}
+
+pub struct UserCache {
+    users: NameTable,
+    groups: NameTable,
+}
+
+impl UserCache {
+    pub fn new() -> Self {
+        Self {
+            users: NameTable::new("/etc/passwd"),
+            groups: NameTable::new("/etc/group"),
+        }
+    }
+
+    pub fn refresh(&mut self) {
+        self.users.refresh();
+        self.groups.refresh();
+    }
+
+    pub fn user_name(&self, uid: u32) -> Option<&str> {
+        self.users.name(uid)
+    }
+
+    pub fn group_name(&self, gid: u32) -> Option<&str> {
+        self.groups.name(gid)
+    }
+
+    /// UID for a user name or a numeric UID
+    pub fn resolve_user(&self, user: &str) -> Option<u32> {
+        user.parse().ok().or_else(|| self.users.id(user))
+    }
+}
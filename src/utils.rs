@@ -1,7 +1,67 @@
+use chrono::{DateTime, Local, NaiveDateTime};
+use unicode_width::UnicodeWidthChar;
+
 pub fn truncate_string(s: &str, max_len: usize) -> String {
-    if s.len() <= max_len {
+    if s.chars().count() <= max_len {
         s.to_string()
     } else {
-        format!("{}...", &s[..max_len-3])
+        let kept: String = s.chars().take(max_len.saturating_sub(3)).collect();
+        format!("{}...", kept)
     }
-}
\ No newline at end of file
+}
+
+/// Parse a user-supplied time as RFC 3339 or local `YYYY-MM-DD HH:MM:SS`
+pub fn parse_timestamp(s: &str) -> Option<DateTime<Local>> {
+    if let Ok(ts) = DateTime::parse_from_rfc3339(s) {
+        return Some(ts.with_timezone(&Local));
+    }
+    NaiveDateTime::parse_from_str(s, "%Y-%m-%d %H:%M:%S").ok()?
+        .and_local_timezone(Local)
+        .earliest()
+}
+
+/// Cut or pad `s` to exactly `width` terminal columns, accounting for wide characters
+pub fn fit_width(s: &str, width: usize) -> String {
+    let mut out = String::new();
+    let mut used = 0;
+    for c in s.chars() {
+        let w = UnicodeWidthChar::width(c).unwrap_or(0);
+        if used + w > width {
+            break;
+        }
+        out.push(c);
+        used += w;
+    }
+    out.extend(std::iter::repeat_n(' ', width - used));
+    out
+}
+
+/// Compact human-readable duration, e.g. `3d 04h`, `1h 02m`, `45s`
+pub fn format_duration(secs: u64) -> String {
+    let (days, hours, minutes) = (secs / 86_400, secs / 3_600 % 24, secs / 60 % 60);
+    if days > 0 {
+        format!("{}d {:02}h", days, hours)
+    } else if hours > 0 {
+        format!("{}h {:02}m", hours, minutes)
+    } else if minutes > 0 {
+        format!("{}m {:02}s", minutes, secs % 60)
+    } else {
+        format!("{}s", secs)
+    }
+}
+
+/// Compact byte count, e.g. `512B`, `1.5K`, `23.4M`
+pub fn format_size(bytes: f64) -> String {
+    const UNITS: &[&str] = &["B", "K", "M", "G", "T"];
+    let mut value = bytes;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{:.0}B", value)
+    } else {
+        format!("{:.1}{}", value, UNITS[unit])
+    }
+}